@@ -0,0 +1,99 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, log};
+
+use crate::*;
+
+const DEFAULT_HOUSE_MARGIN: f64 = 0.05; // 5% overround
+const DEFAULT_VIRTUAL_LIQUIDITY: f64 = 1000.0; // Weighting of the initial bets pools
+const MAX_HOUSE_MARGIN: f64 = 0.25;
+const MIN_VIRTUAL_LIQUIDITY: f64 = 10.0;
+const MAX_VIRTUAL_LIQUIDITY: f64 = 1000000.0;
+const DEFAULT_CANCELLATION_FEE: f64 = 0.02; // 2% of the stake
+const MAX_CANCELLATION_FEE: f64 = 0.5;
+const DEFAULT_ODDS_HISTORY_SIZE: u32 = 100;
+const MAX_ODDS_HISTORY_SIZE: u32 = 1000;
+const DEFAULT_CHALLENGE_WINDOW: u64 = 7200000000000; // Two hours
+const DEFAULT_DISPUTE_BOND: f64 = 10.0;
+
+// Struct that holds the contract level defaults used when pricing a match
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Config {
+    pub house_margin: f64, // Overround added to the odds e.g. 0.05 makes the implied probabilities add to 1.05
+    pub virtual_liquidity: f64, // Amount the initial bets pools are seeded with, more liquidity means odds move less with each bet
+    pub odds_history_size: u32, // Number of odds snapshots kept for each match, the oldest are overwritten
+    pub odds_snapshot_interval: U64, // Nanoseconds that must pass between odds snapshots, 0 takes a snapshot on every bet
+    pub cancellation_fee: f64, // Fraction of the stake kept by the house when a bet is cancelled
+    pub challenge_window: U64, // Nanoseconds a proposed result can be disputed for
+    pub dispute_bond: f64, // Smallest bond that must be sent to dispute a proposed result
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self{house_margin: DEFAULT_HOUSE_MARGIN, virtual_liquidity: DEFAULT_VIRTUAL_LIQUIDITY, odds_history_size: DEFAULT_ODDS_HISTORY_SIZE, odds_snapshot_interval: U64(0), cancellation_fee: DEFAULT_CANCELLATION_FEE, challenge_window: U64(DEFAULT_CHALLENGE_WINDOW), dispute_bond: DEFAULT_DISPUTE_BOND}
+    }
+}
+
+impl Config {
+    // Panics if any value is outside of its bounds
+    pub fn validate(&self) {
+        if let Some(error) = self.error() {
+            panic!("{}", error)
+        }
+    }
+
+    // Returns why a value is outside of its bounds, or None if every value is in bounds
+    pub fn error(&self) -> Option<String> {
+        if !(0.0..=MAX_HOUSE_MARGIN).contains(&self.house_margin) {
+            return Some(format!("The house margin must be between 0 and {}", MAX_HOUSE_MARGIN))
+        }
+        if !(MIN_VIRTUAL_LIQUIDITY..=MAX_VIRTUAL_LIQUIDITY).contains(&self.virtual_liquidity) {
+            return Some(format!("The virtual liquidity must be between {} and {}", MIN_VIRTUAL_LIQUIDITY, MAX_VIRTUAL_LIQUIDITY))
+        }
+        if !(0.0..=MAX_CANCELLATION_FEE).contains(&self.cancellation_fee) {
+            return Some(format!("The cancellation fee must be between 0 and {}", MAX_CANCELLATION_FEE))
+        }
+        if !(1..=MAX_ODDS_HISTORY_SIZE).contains(&self.odds_history_size) {
+            return Some(format!("The odds history size must be between 1 and {}", MAX_ODDS_HISTORY_SIZE))
+        }
+        if self.dispute_bond < 0.0 {
+            return Some("The dispute bond can't be negative".to_string())
+        }
+        None
+    }
+
+    // Returns the config with the per match overrides applied on top, panics if the result is out of bounds
+    pub fn with_overrides(&self, house_margin: Option<f64>, virtual_liquidity: Option<f64>) -> Config {
+        let config: Config = self.overridden(house_margin, virtual_liquidity);
+        config.validate();
+        config
+    }
+
+    // Returns the config with the per match overrides applied on top without checking it
+    pub fn overridden(&self, house_margin: Option<f64>, virtual_liquidity: Option<f64>) -> Config {
+        Config{
+            house_margin: house_margin.unwrap_or(self.house_margin),
+            virtual_liquidity: virtual_liquidity.unwrap_or(self.virtual_liquidity),
+            ..*self
+        }
+    }
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the contract config
+
+    // Private call function that allows the contract account to change the defaults used by new matches
+    #[private]
+    pub fn set_config(&mut self, config: Config) {
+        config.validate();
+        self.config = config;
+        log!("The house margin is now {}, the virtual liquidity is now {}, {} odds snapshots are kept every {} nanoseconds, the cancellation fee is {}, results can be disputed for {} nanoseconds and the dispute bond is {}", config.house_margin, config.virtual_liquidity, config.odds_history_size, config.odds_snapshot_interval.0, config.cancellation_fee, config.challenge_window.0, config.dispute_bond)
+    }
+
+    // View function that allows the user to view the defaults used by new matches
+    pub fn view_config(&self) -> Config {
+        self.config
+    }
+}
@@ -0,0 +1,158 @@
+#![allow(clippy::too_many_arguments)] // near_bindgen only passes cfg attributes on to the MatchListExt function it generates for create_event
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, log};
+
+use crate::*;
+use crate::markets::Market;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum EventState {
+    Active,
+    BettingClosed, // Every match and futures market in the event has stopped taking bets
+    Cancelled, // Every bet in the event has been returned
+}
+
+// Struct that holds the details of a tournament and the matches and futures markets played at it
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Event {
+    sku: String, // RobotEvents SKU e.g. "RE-VRC-23-1234", used as the event's id
+    name: String,
+    season: String, // e.g. "Over Under"
+    location: String,
+    start_date: String,
+    end_date: String,
+    divisions: Vec<String>, // Divisions matches can be played in e.g. "Science"
+    match_ids: Vec<String>, // Matches played at the event
+    market_ids: Vec<u64>, // Futures markets and propositions on the event
+    event_state: EventState,
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of events
+
+    // Private call function that allows the contract account to add an event that matches and futures markets can be created in
+    #[private]
+    pub fn create_event(&mut self, sku: String, name: String, season: String, location: String, start_date: String, end_date: String, divisions: Vec<String>) {
+        require!(!sku.is_empty(), "The event needs a SKU");
+        require!(self.events.get(&sku).is_none(), "An event already exists with that SKU"); // Stops an event from being overwritten
+        require!(!divisions.is_empty(), "The event needs at least one division");
+
+        let event: Event = Event{sku: sku.clone(), name, season, location, start_date, end_date, divisions, match_ids: Vec::new(), market_ids: Vec::new(), event_state: EventState::Active};
+        self.events.insert(&sku, &event);
+        log!("Event {} has been added", sku)
+    }
+
+    // Private call function that closes betting on every future match and open market in an event, done when the event starts its final matches
+    #[private]
+    pub fn close_event_betting(&mut self, sku: String) {
+        let mut event: Event = self.events.get(&sku).unwrap_or_else(|| panic!("No event exists with SKU: {}", sku));
+        require!(event.event_state == EventState::Active, "Betting has already closed for that event");
+
+        for match_id in event.match_ids.iter() {
+            if self.future_matches.get(match_id).is_some() {
+                self.end_betting(match_id.clone());
+            }
+        }
+        for market_id in event.market_ids.iter() {
+            let mut market: Market = self.markets.get(market_id).unwrap();
+            if market.is_open() {
+                market.close();
+                self.markets.insert(market_id, &market);
+            }
+        }
+
+        event.event_state = EventState::BettingClosed;
+        self.events.insert(&sku, &event);
+        log!("Betting has closed for event {}", sku)
+    }
+
+    // Private call function that returns every bet on the matches and markets of an event that hasn't been settled, done when the event is cancelled
+    #[private]
+    pub fn void_event(&mut self, sku: String) {
+        let mut event: Event = self.events.get(&sku).unwrap_or_else(|| panic!("No event exists with SKU: {}", sku));
+        require!(event.event_state != EventState::Cancelled, "That event has already been cancelled");
+
+        for match_id in event.match_ids.iter() {
+            if self.future_matches.get(match_id).is_some() {
+                self.return_funds(match_id.clone(), MatchState::Future);
+            } else if self.in_progress_matches.get(match_id).is_some() {
+                self.return_funds(match_id.clone(), MatchState::InProgress);
+            }
+        }
+        for market_id in event.market_ids.iter() {
+            let mut market: Market = self.markets.get(market_id).unwrap();
+            if !market.is_settled() {
+                self.settle_market(&mut market, None);
+                self.markets.insert(market_id, &market);
+            }
+        }
+
+        event.event_state = EventState::Cancelled;
+        self.events.insert(&sku, &event);
+        log!("Event {} has been cancelled and its bets returned", sku)
+    }
+
+    // View function that allows the user to view a single event
+    pub fn view_event(&self, sku: String) -> Event {
+        self.events.get(&sku).unwrap_or_else(|| panic!("No event exists with SKU: {}", sku))
+    }
+
+    // View function that allows the user to view every event
+    pub fn view_events(&self) -> Vec<Event> {
+        self.events.values().collect()
+    }
+
+    // View function that allows the user to view every match, in any state, in an event
+    pub fn view_event_matches(&self, sku: String) -> Vec<MatchView> {
+        let event: Event = self.events.get(&sku).unwrap_or_else(|| panic!("No event exists with SKU: {}", sku));
+        event.match_ids.into_iter()
+            .filter_map(|match_id| self.find_match(&match_id).map(|current_match| current_match.view(match_id)))
+            .collect()
+    }
+}
+
+
+impl MatchList { // Helper functions for events
+
+    // Adds a match to an event, panics if the event isn't taking new matches or doesn't have the division
+    pub(crate) fn add_event_match(&mut self, sku: &String, division: &String, match_id: String) {
+        if let Some(error) = self.event_match_error(sku, division) {
+            panic!("{}", error)
+        }
+        let mut event: Event = self.events.get(sku).unwrap();
+        event.match_ids.push(match_id);
+        self.events.insert(sku, &event);
+    }
+
+    // Returns why a match can't be added to an event, or None if it can
+    pub(crate) fn event_match_error(&self, sku: &String, division: &String) -> Option<String> {
+        match self.events.get(sku) {
+            None => Some(format!("No event exists with SKU: {}", sku)),
+            Some(event) if event.event_state != EventState::Active => Some("That event isn't taking new matches".to_string()),
+            Some(event) if !event.divisions.contains(division) => Some("That event has no such division".to_string()),
+            Some(_) => None,
+        }
+    }
+
+    // Adds a futures market or proposition to an event, panics if the event isn't taking new markets
+    pub(crate) fn add_event_market(&mut self, sku: &String, market_id: u64) {
+        let mut event: Event = self.events.get(sku).unwrap_or_else(|| panic!("No event exists with SKU: {}", sku));
+        require!(event.event_state == EventState::Active, "That event isn't taking new markets");
+        event.market_ids.push(market_id);
+        self.events.insert(sku, &event);
+    }
+}
+
+
+impl Event { // Helper functions for a single event
+
+    // Returns the ids of the matches played at the event
+    pub(crate) fn match_ids(&self) -> &Vec<String> {
+        &self.match_ids
+    }
+}
@@ -0,0 +1,340 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+const DUST: f64 = 0.000000001; // Amounts smaller than this are treated as zero to avoid floating point leftovers
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OrderSide {
+    Back, // Bets that the outcome wins, the amount escrowed is the stake
+    Lay, // Bets that the outcome loses, the amount escrowed is the liability, which is the backer's stake multiplied by the odds minus one
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OrderState {
+    Open,
+    Filled,
+    Cancelled, // Cancelled by the user or returned when betting ended
+}
+
+// Struct that holds an offer on the order book of an exchange match
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Order {
+    order_id: u64,
+    account: AccountId,
+    outcome: AllianceColour,
+    side: OrderSide,
+    odds: f64, // Worst decimal odds the user will accept, backers want at least these odds and layers at most these odds
+    remaining: f64, // Unmatched escrow, the stake for a back order and the liability for a lay order
+    order_state: OrderState,
+}
+
+// Struct that holds a bet between a backer and a layer once their orders have been matched
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchedBet {
+    backer: AccountId,
+    layer: AccountId,
+    outcome: AllianceColour,
+    odds: f64,
+    stake: f64, // The backer's stake, the layer's liability is stake * (odds - 1)
+    payed_out: PayedOut,
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the back and lay exchange
+
+    // Call function that allows a user to cancel the unmatched part of one of their orders before betting ends
+    pub fn cancel_order(&mut self, match_id: String, order_id: u64) {
+        let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let order: &mut Order = current_match.orders.get_mut(order_id as usize).unwrap_or_else(|| panic!("No order exists with order_id: {}", order_id));
+
+        require!(order.account == env::predecessor_account_id(), "You can only cancel your own orders");
+        require!(order.order_state == OrderState::Open, "That order is already filled or cancelled");
+
+        transfer_usdc(order.account.clone(), order.remaining, "Cancelled order", near_sdk::Gas(30000000000000)); // Returns the unmatched escrow
+        order.remaining = 0.0;
+        order.order_state = OrderState::Cancelled;
+
+        self.future_matches.insert(&match_id, &current_match); // Updates the match
+        log!("Order {} has been cancelled", order_id)
+    }
+
+    // View function that allows the user to view the open orders for one alliance of an exchange match
+    pub fn view_order_book(&self, match_id: String, outcome: AllianceColour) -> Vec<Order> {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        current_match.orders.into_iter()
+            .filter(|order| order.outcome == outcome && order.order_state == OrderState::Open)
+            .collect()
+    }
+
+    // View function that allows the user to view the matched bets of an exchange match
+    // Input either an account to view the bets it is the backer or layer of, or "all" to view all matched bets
+    pub fn view_matched_bets(&self, match_id: String, name: String) -> Vec<MatchedBet> {
+        let current_match: Match = self.find_match(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        current_match.matched_bets.into_iter()
+            .filter(|bet| name == "all" || bet.backer.as_str() == name || bet.layer.as_str() == name)
+            .collect()
+    }
+}
+
+
+impl Match { // Helper functions for exchange matches
+
+    // Matches a new order against the order book, anything left unmatched rests on the book
+    // Orders are matched at the odds of the resting order, best odds first and then oldest first
+    pub(crate) fn place_order(&mut self, account: AccountId, outcome: AllianceColour, side: OrderSide, odds: f64, amount: f64) {
+        require!(odds > 1.0, "The odds must be greater than 1");
+        let mut remaining: f64 = amount;
+
+        // Finds the resting orders on the other side of the book that the new order would accept
+        let mut candidates: Vec<usize> = (0..self.orders.len())
+            .filter(|i| {
+                let order: &Order = &self.orders[*i];
+                order.order_state == OrderState::Open && order.outcome == outcome && order.side != side && match side {
+                    OrderSide::Back => order.odds >= odds,
+                    OrderSide::Lay => order.odds <= odds,
+                }
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            let (a, b): (&Order, &Order) = (&self.orders[*a], &self.orders[*b]);
+            let better_odds = match side {
+                OrderSide::Back => b.odds.partial_cmp(&a.odds), // Backers want the highest odds
+                OrderSide::Lay => a.odds.partial_cmp(&b.odds), // Layers want the lowest odds
+            };
+            better_odds.unwrap().then(a.order_id.cmp(&b.order_id))
+        });
+
+        for i in candidates {
+            if remaining < DUST {
+                break
+            }
+            let resting: &mut Order = &mut self.orders[i];
+            let matched_odds: f64 = resting.odds;
+
+            // Finds the backer's stake that is matched between the two orders
+            let stake: f64 = match side {
+                OrderSide::Back => remaining.min(resting.remaining / (matched_odds - 1.0)),
+                OrderSide::Lay => resting.remaining.min(remaining / (matched_odds - 1.0)),
+            };
+            let liability: f64 = stake * (matched_odds - 1.0);
+            let (backer, layer): (AccountId, AccountId) = match side {
+                OrderSide::Back => {
+                    remaining -= stake;
+                    resting.remaining -= liability;
+                    (account.clone(), resting.account.clone())
+                }
+                OrderSide::Lay => {
+                    remaining -= liability;
+                    resting.remaining -= stake;
+                    (resting.account.clone(), account.clone())
+                }
+            };
+            if resting.remaining < DUST {
+                resting.order_state = OrderState::Filled;
+            }
+
+            self.matched_bets.push(MatchedBet{backer, layer, outcome, odds: matched_odds, stake, payed_out: PayedOut::YetToBePayed});
+        }
+
+        // The price the new order was placed at may have been better than what it asked for, so for a lay order some liability is left
+        let order_id: u64 = self.orders.len() as u64;
+        let order_state: OrderState = if remaining < DUST { OrderState::Filled } else { OrderState::Open };
+        self.orders.push(Order{order_id, account, outcome, side, odds, remaining, order_state});
+        log!("Order {} has been placed, {} is left unmatched", order_id, remaining)
+    }
+
+    // Returns the unmatched escrow of every open order, done when betting ends
+    pub(crate) fn return_open_orders(&mut self) {
+        for order in self.orders.iter_mut().filter(|order| order.order_state == OrderState::Open) {
+            transfer_usdc(order.account.clone(), order.remaining, "Unmatched order", near_sdk::Gas(30000000000000));
+            order.remaining = 0.0;
+            order.order_state = OrderState::Cancelled;
+        }
+    }
+
+    // Pays out up to limit matched bets once the match has finished, only those of the account if one is given
+    // The commission is taken from the net winnings, a tied match returns both sides. Returns the number of matched bets payed out
+    pub(crate) fn settle_matched_bets(&mut self, winner: Option<AllianceColour>, limit: usize, account: Option<&AccountId>) -> usize {
+        let commission: f64 = self.house_margin;
+        let mut processed: usize = 0;
+        for bet in self.matched_bets.iter_mut().filter(|bet| bet.payed_out == PayedOut::YetToBePayed && account.is_none_or(|account| &bet.backer == account || &bet.layer == account)) {
+            if processed == limit {
+                break
+            }
+            let Some(winning_alliance) = winner else { // Push
+                bet.return_funds();
+                processed += 1;
+                continue
+            };
+            let (winner, net_winnings): (AccountId, f64) = if bet.outcome == winning_alliance {
+                (bet.backer.clone(), bet.stake * (bet.odds - 1.0)) // The backer wins the layer's liability
+            } else {
+                (bet.layer.clone(), bet.stake) // The layer wins the backer's stake
+            };
+            let escrow: f64 = bet.stake * bet.odds; // Both sides of the bet
+            transfer_usdc(winner, escrow - net_winnings * commission, "Winnings", near_sdk::Gas(30000000000000));
+            bet.payed_out = PayedOut::Payed;
+            processed += 1;
+        }
+        processed
+    }
+
+    // Returns how much the account has escrowed on the exchange, the unmatched part of its open orders and its side of its matched bets
+    pub(crate) fn exchange_stake(&self, account: &AccountId) -> f64 {
+        let unmatched: f64 = self.orders.iter()
+            .filter(|order| &order.account == account && order.order_state == OrderState::Open)
+            .map(|order| order.remaining)
+            .sum();
+        let matched: f64 = self.matched_bets.iter()
+            .filter(|bet| bet.payed_out == PayedOut::YetToBePayed)
+            .map(|bet| {
+                let backed: f64 = if &bet.backer == account { bet.stake } else { 0.0 };
+                let laid: f64 = if &bet.layer == account { bet.stake * (bet.odds - 1.0) } else { 0.0 };
+                backed + laid
+            })
+            .sum();
+        unmatched + matched
+    }
+
+    // Returns the number of matched bets that are yet to be payed out
+    pub(crate) fn unpaid_matched_bets(&self) -> usize {
+        self.matched_bets.iter().filter(|bet| bet.payed_out == PayedOut::YetToBePayed).count()
+    }
+
+    // Returns both sides of every matched bet and any unmatched escrow, done when the match is cancelled
+    pub(crate) fn return_exchange_funds(&mut self) {
+        self.return_open_orders();
+        for bet in self.matched_bets.iter_mut().filter(|bet| bet.payed_out == PayedOut::YetToBePayed) {
+            bet.return_funds();
+        }
+    }
+
+    // Returns the best odds each alliance can currently be backed at, which come from the open lay orders, 0 if there are none
+    pub(crate) fn best_back_odds(&self) -> Vec<f64> {
+        let mut best_odds: Vec<f64> = vec![0.0; 2];
+        for order in self.orders.iter().filter(|order| order.order_state == OrderState::Open && order.side == OrderSide::Lay) {
+            let best: &mut f64 = &mut best_odds[order.outcome.index()];
+            *best = best.max(order.odds);
+        }
+        best_odds
+    }
+}
+
+
+impl MatchedBet {
+    // Returns the backer's stake and the layer's liability
+    fn return_funds(&mut self) {
+        transfer_usdc(self.backer.clone(), self.stake, "Return funds", near_sdk::Gas(30000000000000));
+        transfer_usdc(self.layer.clone(), self.stake * (self.odds - 1.0), "Return funds", near_sdk::Gas(30000000000000));
+        self.payed_out = PayedOut::ReturnPay;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange_match() -> Match {
+        Match{
+            bets: Vec::new(),
+            red_alliance: Alliance{colour: AllianceColour::Red, teams: vec!["1234A".to_string()]},
+            blue_alliance: Alliance{colour: AllianceColour::Blue, teams: vec!["5678B".to_string()]},
+            event: None,
+            division: "Science".to_string(),
+            round: 1,
+            match_number: 1,
+            metadata: MatchMetadata::default(),
+            red_total_bets: 0.0,
+            blue_total_bets: 0.0,
+            promised_winnings: 0.0,
+            house_margin: 0.0,
+            virtual_liquidity: 0.0,
+            pricing: PricingKind::Exchange,
+            orders: Vec::new(),
+            matched_bets: Vec::new(),
+            odds_history: OddsHistory::new(0, 0),
+            in_play: None,
+            winner: None,
+            red_score: None,
+            blue_score: None,
+            proposed_result: None,
+            match_state: MatchState::Future,
+        }
+    }
+
+    fn account(name: &str) -> AccountId {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn back_order_partially_fills_at_the_resting_odds() {
+        let mut current_match: Match = exchange_match();
+        current_match.place_order(account("layer.near"), AllianceColour::Red, OrderSide::Lay, 3.0, 20.0); // Covers a backer's stake of 10
+        current_match.place_order(account("backer.near"), AllianceColour::Red, OrderSide::Back, 2.5, 15.0);
+
+        assert_eq!(current_match.matched_bets.len(), 1);
+        let bet: &MatchedBet = &current_match.matched_bets[0];
+        assert_eq!(bet.odds, 3.0); // The backer gets the better odds of the resting order
+        assert_eq!(bet.stake, 10.0);
+        assert_eq!(bet.backer, account("backer.near"));
+        assert_eq!(bet.layer, account("layer.near"));
+
+        assert!(current_match.orders[0].order_state == OrderState::Filled);
+        assert!(current_match.orders[1].order_state == OrderState::Open);
+        assert_eq!(current_match.orders[1].remaining, 5.0); // The rest of the stake waits on the book
+
+        assert_eq!(current_match.exchange_stake(&account("backer.near")), 15.0);
+        assert_eq!(current_match.exchange_stake(&account("layer.near")), 20.0);
+    }
+
+    #[test]
+    fn lay_order_partially_fills_at_the_resting_odds() {
+        let mut current_match: Match = exchange_match();
+        current_match.place_order(account("backer.near"), AllianceColour::Blue, OrderSide::Back, 2.0, 10.0);
+        current_match.place_order(account("layer.near"), AllianceColour::Blue, OrderSide::Lay, 3.0, 5.0);
+
+        let bet: &MatchedBet = &current_match.matched_bets[0];
+        assert_eq!(bet.odds, 2.0);
+        assert_eq!(bet.stake, 5.0); // A liability of 5 at odds of 2 covers a stake of 5
+
+        assert!(current_match.orders[0].order_state == OrderState::Open);
+        assert_eq!(current_match.orders[0].remaining, 5.0);
+        assert!(current_match.orders[1].order_state == OrderState::Filled);
+    }
+
+    #[test]
+    fn best_odds_are_matched_first() {
+        let mut current_match: Match = exchange_match();
+        current_match.place_order(account("first.near"), AllianceColour::Red, OrderSide::Lay, 2.0, 10.0);
+        current_match.place_order(account("second.near"), AllianceColour::Red, OrderSide::Lay, 3.0, 10.0);
+        current_match.place_order(account("backer.near"), AllianceColour::Red, OrderSide::Back, 1.5, 8.0);
+
+        assert_eq!(current_match.matched_bets.len(), 2);
+        assert_eq!(current_match.matched_bets[0].layer, account("second.near"));
+        assert_eq!(current_match.matched_bets[0].stake, 5.0); // All of the second layer's liability of 10 at odds of 3
+        assert_eq!(current_match.matched_bets[1].layer, account("first.near"));
+        assert_eq!(current_match.matched_bets[1].stake, 3.0);
+        assert!(current_match.orders[2].order_state == OrderState::Filled);
+    }
+
+    #[test]
+    fn orders_that_dont_cross_rest_on_the_book() {
+        let mut current_match: Match = exchange_match();
+        current_match.place_order(account("layer.near"), AllianceColour::Red, OrderSide::Lay, 2.0, 10.0);
+        current_match.place_order(account("backer.near"), AllianceColour::Red, OrderSide::Back, 2.5, 10.0); // Wants better odds than are on offer
+        current_match.place_order(account("other.near"), AllianceColour::Blue, OrderSide::Back, 1.5, 10.0); // Other outcome
+
+        assert!(current_match.matched_bets.is_empty());
+        assert!(current_match.orders.iter().all(|order| order.order_state == OrderState::Open));
+        assert_eq!(current_match.best_back_odds(), vec![2.0, 0.0]);
+    }
+}
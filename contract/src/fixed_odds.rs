@@ -0,0 +1,77 @@
+use near_sdk::{near_bindgen, require, log};
+
+use crate::*;
+
+#[near_bindgen]
+impl MatchList { // Implementation of the operator managed fixed odds mode
+
+    // Private call function that allows the contract account to change the odds of a future fixed odds match
+    // Bets that have already been placed keep the odds they were placed at
+    #[private]
+    pub fn update_fixed_odds(&mut self, match_id: String, new_odds: Vec<f64>) {
+        let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+
+        let version: u32 = match &mut current_match.pricing {
+            PricingKind::FixedOdds { odds, odds_version, .. } => {
+                *odds = new_odds;
+                *odds_version += 1;
+                *odds_version
+            }
+            _ => panic!("That match doesn't use fixed odds"),
+        };
+        current_match.pricing.validate(2); // Checks the new odds are valid
+        current_match.record_odds();
+
+        self.future_matches.insert(&match_id, &current_match); // Updates the match
+        log!("The odds for {} have been updated to version {}", match_id, version)
+    }
+
+    // Private call function that allows the contract account to change the liability limits of a future fixed odds match
+    // Input null to remove the limits
+    #[private]
+    pub fn set_liability_limits(&mut self, match_id: String, new_limits: Option<Vec<f64>>) {
+        let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+
+        require!(new_limits.is_none() || self.match_parlays.get(&match_id).is_none(), "That match has parlays on it, which aren't counted in its liabilities");
+
+        match &mut current_match.pricing {
+            PricingKind::FixedOdds { liability_limits, .. } => *liability_limits = new_limits,
+            _ => panic!("That match doesn't use fixed odds"),
+        }
+        current_match.pricing.validate(2); // Checks the new limits are valid
+
+        self.future_matches.insert(&match_id, &current_match); // Updates the match
+        log!("The liability limits for {} have been updated", match_id)
+    }
+
+    // View function that allows the user to view how much the house would lose on each alliance winning a future match
+    pub fn view_liabilities(&self, match_id: String) -> Vec<f64> {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        current_match.liabilities()
+    }
+}
+
+
+impl Match { // Helper functions for fixed odds matches
+
+    // Panics if a bet would take the house's liability on the alliance over its limit
+    pub(crate) fn check_liability_limit(&self, decision: AllianceColour, bet_amount: f64, potential_winnings: f64) {
+        require!(self.within_liability_limit(decision, bet_amount, potential_winnings), "Bets on that alliance are no longer being accepted at these odds");
+    }
+
+    // Returns true if the match uses fixed odds with liability limits
+    pub(crate) fn has_liability_limits(&self) -> bool {
+        matches!(self.pricing, PricingKind::FixedOdds { liability_limits: Some(_), .. })
+    }
+
+    // Returns false if a bet would take the house's liability on the alliance over its limit
+    pub(crate) fn within_liability_limit(&self, decision: AllianceColour, bet_amount: f64, potential_winnings: f64) -> bool {
+        match &self.pricing {
+            PricingKind::FixedOdds { liability_limits: Some(liability_limits), .. } => {
+                let liability: f64 = self.liabilities()[decision.index()] + potential_winnings - bet_amount; // Liability on the alliance once the bet is placed
+                liability <= liability_limits[decision.index()]
+            }
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use near_sdk::{near_bindgen, require, log};
+
+use crate::*;
+use crate::events::Event;
+use crate::markets::{Market, MarketKind, MarketView};
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the tournament futures markets
+
+    // Private call function that allows the contract account to open a futures market on who wins an event or division, input the SKU of the event
+    // The outcomes default to every team playing in the event's matches, and if no odds are input they are found from the ratings of the teams
+    // The house margin and virtual liquidity can be set for this market, otherwise the contract defaults are used
+    #[private]
+    pub fn create_futures_market(&mut self, event: String, outcomes: Option<Vec<String>>, in_odds: Option<Vec<f64>>, pricing: Option<PricingKind>, house_margin: Option<f64>, virtual_liquidity: Option<f64>) {
+        let market_config: Config = self.config.with_overrides(house_margin, virtual_liquidity); // Applies the overrides for this market and checks they are in bounds
+        let outcomes: Vec<String> = outcomes.unwrap_or_else(|| self.event_teams(&event));
+
+        // Uses the team registry when every outcome is a registered team, otherwise every outcome starts even
+        let in_odds: Option<Vec<f64>> = in_odds.or_else(|| self.field_probabilities(&outcomes).map(|probabilities| probabilities.iter().map(|prob| 1.0 / prob).collect()));
+
+        let market_id: u64 = self.create_market(None, MarketKind::Futures { event: event.clone() }, outcomes, in_odds, pricing, market_config);
+        self.add_event_market(&event, market_id); // Panics if the event doesn't exist
+        log!("Futures market {} has been opened for {}", market_id, event)
+    }
+
+    // Call function that allows a reporter to knock outcomes out of a futures market, such as teams eliminated from the event
+    // The bets on those outcomes are lost and the odds of the rest are found from the outcomes still in the market
+    pub fn eliminate_outcomes(&mut self, market_id: u64, outcomes: Vec<usize>) {
+        self.assert_reporter();
+        let mut market: Market = self.futures_market(market_id);
+        require!(!market.is_settled(), "That market has already been settled");
+
+        self.bet_counter -= market.reserved(); // Takes off the reserved winnings as they will change
+        for outcome in outcomes.iter() {
+            market.eliminate(*outcome);
+        }
+        self.bet_counter += market.reserved(); // Adds this back on with changed amount
+
+        self.markets.insert(&market_id, &market);
+        log!("{} outcomes have been eliminated from market {}", outcomes.len(), market_id)
+    }
+
+    // Call function that allows a reporter to stop taking bets on a futures market, such as when the finals start
+    pub fn close_futures_market(&mut self, market_id: u64) {
+        self.assert_reporter();
+        let mut market: Market = self.futures_market(market_id);
+        market.close();
+        self.markets.insert(&market_id, &market);
+        log!("Betting has closed for market {}", market_id)
+    }
+
+    // Call function that allows a reporter to settle a futures market once the event has finished
+    // Input the index of the winning outcome, or null to void the market and return the stakes that haven't been lost
+    pub fn settle_futures_market(&mut self, market_id: u64, winning_outcome: Option<usize>) {
+        self.assert_reporter();
+        let mut market: Market = self.futures_market(market_id);
+        require!(!market.is_settled(), "That market has already been settled");
+        require!(winning_outcome.is_none_or(|outcome| market.in_running(outcome)), "The winner must be an outcome that hasn't been eliminated");
+
+        self.settle_market(&mut market, winning_outcome);
+        self.markets.insert(&market_id, &market);
+        log!("Market {} has been settled", market_id)
+    }
+
+    // View function that allows the user to view every futures market
+    pub fn view_futures_markets(&self) -> Vec<MarketView> {
+        self.markets.values().filter(|market| market.is_futures()).map(|market| market.view()).collect()
+    }
+}
+
+
+impl MatchList { // Helper functions for the futures markets
+
+    // Returns every team that plays in a match of the event, in the order they first appear
+    fn event_teams(&self, sku: &String) -> Vec<String> {
+        let event: Event = self.events.get(sku).unwrap_or_else(|| panic!("No event exists with SKU: {}", sku));
+        let mut teams: Vec<String> = Vec::new();
+        for current_match in event.match_ids().iter().filter_map(|match_id| self.find_match(match_id)) {
+            for team_number in current_match.red_alliance.teams.into_iter().chain(current_match.blue_alliance.teams) {
+                if !teams.contains(&team_number) {
+                    teams.push(team_number);
+                }
+            }
+        }
+        require!(!teams.is_empty(), "The event has no matches yet, input the outcomes instead");
+        teams
+    }
+
+    // Returns a futures market, panics if it doesn't exist or is a market on a match
+    fn futures_market(&self, market_id: u64) -> Market {
+        let market: Market = self.markets.get(&market_id).unwrap_or_else(|| panic!("No market exists with market_id: {}", market_id));
+        require!(market.is_futures(), "That isn't a futures market");
+        market
+    }
+}
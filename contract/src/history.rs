@@ -0,0 +1,150 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::Serialize;
+use near_sdk::json_types::U64;
+use near_sdk::{env, near_bindgen};
+
+use crate::*;
+
+// Struct that holds the state of a match's market at one point in time
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OddsSnapshot {
+    timestamp: U64, // Block timestamp in nanoseconds
+    pools: Vec<f64>, // Pools of the pricing model, red first then blue
+    odds: Vec<f64>, // Unrounded decimal odds, red first then blue
+}
+
+// Ring buffer of odds snapshots for a match, once full the oldest snapshot is overwritten
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OddsHistory {
+    snapshots: Vec<OddsSnapshot>,
+    next: u32, // Index the next snapshot is written to once the buffer is full
+    capacity: u32,
+    interval: u64, // Nanoseconds that must pass between snapshots
+}
+
+impl OddsHistory {
+    pub fn new(capacity: u32, interval: u64) -> Self {
+        Self{snapshots: Vec::new(), next: 0, capacity, interval}
+    }
+
+    // Adds a snapshot if enough time has passed since the last one
+    pub fn record(&mut self, pools: Vec<f64>, odds: Vec<f64>) {
+        let timestamp: u64 = env::block_timestamp();
+        if let Some(last) = self.latest() {
+            if timestamp < last.timestamp.0 + self.interval {
+                return
+            }
+        }
+
+        let snapshot: OddsSnapshot = OddsSnapshot{timestamp: U64(timestamp), pools, odds};
+        if (self.snapshots.len() as u32) < self.capacity {
+            self.snapshots.push(snapshot);
+        } else {
+            self.snapshots[self.next as usize] = snapshot; // Overwrites the oldest snapshot
+            self.next = (self.next + 1) % self.capacity;
+        }
+    }
+
+    // Returns the most recent snapshot
+    fn latest(&self) -> Option<&OddsSnapshot> {
+        if self.snapshots.is_empty() {
+            return None
+        }
+        let latest: usize = (self.next as usize + self.snapshots.len() - 1) % self.snapshots.len();
+        self.snapshots.get(latest)
+    }
+
+    // Returns the snapshots from oldest to newest
+    fn in_order(&self) -> impl Iterator<Item = &OddsSnapshot> {
+        let (newer, older) = self.snapshots.split_at(self.next as usize);
+        older.iter().chain(newer.iter())
+    }
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the odds history
+
+    // View function that allows the user to view how the odds of a match have moved, oldest first
+    // Returns up to limit snapshots taken at or after the from timestamp in nanoseconds
+    pub fn view_odds_history(&self, match_id: String, from: U64, limit: u32) -> Vec<OddsSnapshot> {
+        let current_match: Match = self.find_match(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        current_match.odds_history.in_order()
+            .filter(|snapshot| snapshot.timestamp.0 >= from.0)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+}
+
+
+impl Match { // Helper functions for the odds history
+
+    // Takes a snapshot of the current pools and odds
+    pub(crate) fn record_odds(&mut self) {
+        let pools: Vec<f64> = self.pools();
+        let odds: Vec<f64> = self.current_odds();
+        self.odds_history.record(pools, odds);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    // Records a snapshot at the timestamp, the red pool is the timestamp so snapshots can be told apart
+    fn record_at(history: &mut OddsHistory, timestamp: u64) {
+        testing_env!(VMContextBuilder::new().block_timestamp(timestamp).build());
+        history.record(vec![timestamp as f64, 0.0], vec![2.0, 2.0]);
+    }
+
+    fn timestamps(history: &OddsHistory) -> Vec<u64> {
+        history.in_order().map(|snapshot| snapshot.timestamp.0).collect()
+    }
+
+    #[test]
+    fn oldest_snapshots_are_overwritten_once_full() {
+        let mut history: OddsHistory = OddsHistory::new(3, 0);
+        for timestamp in 1..=5 {
+            record_at(&mut history, timestamp);
+        }
+
+        assert_eq!(history.snapshots.len(), 3);
+        assert_eq!(timestamps(&history), vec![3, 4, 5]);
+        assert_eq!(history.latest().unwrap().timestamp.0, 5);
+        assert_eq!(history.latest().unwrap().pools[0], 5.0);
+    }
+
+    #[test]
+    fn wraps_around_more_than_once() {
+        let mut history: OddsHistory = OddsHistory::new(2, 0);
+        for timestamp in 1..=7 {
+            record_at(&mut history, timestamp);
+        }
+
+        assert_eq!(timestamps(&history), vec![6, 7]);
+        assert_eq!(history.latest().unwrap().timestamp.0, 7);
+    }
+
+    #[test]
+    fn snapshots_closer_than_the_interval_are_skipped() {
+        let mut history: OddsHistory = OddsHistory::new(10, 100);
+        for timestamp in [1000, 1050, 1100, 1199, 1200] {
+            record_at(&mut history, timestamp);
+        }
+
+        assert_eq!(timestamps(&history), vec![1000, 1100, 1200]);
+    }
+
+    #[test]
+    fn an_empty_history_has_no_latest_snapshot() {
+        let history: OddsHistory = OddsHistory::new(3, 0);
+        assert!(history.latest().is_none());
+        assert!(timestamps(&history).is_empty());
+    }
+}
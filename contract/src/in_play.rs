@@ -0,0 +1,253 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::U64;
+use near_sdk::{env, AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+// Struct that holds the live state of a match in progress, pushed by the operator or a reporter
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LiveState {
+    pub autonomous_winner: Option<AllianceColour>, // Alliance that won the autonomous bonus, null if it isn't known yet or was tied
+    pub red_score: u32,
+    pub blue_score: u32,
+    pub red_probability: f64, // Chance of the red alliance winning from here, used to price in-play bets
+}
+
+// Struct that holds the in-play phase of a match
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InPlay {
+    open: bool, // Whether in-play bets are being taken
+    live_state: LiveState,
+    state_version: u32, // Goes up every time the live state is updated
+    updated_at: U64, // Block timestamp of the last update
+    red_pool: f64, // Live pool of the pricing model for the red alliance, reseeded on every update
+    blue_pool: f64,
+    promised_winnings: f64, // The promised_winnings of the in-play bets, counted separately from the pre-match bets
+    max_stake: f64, // Largest amount that can be bet at once in-play
+    acceptance_delay: U64, // Nanoseconds a bet has to wait before it is priced
+    pending_bets: Vec<PendingBet>, // Bets waiting out the acceptance delay
+}
+
+// Struct that holds an in-play bet that hasn't been priced yet
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingBet {
+    bettor: AccountId,
+    decision: AllianceColour,
+    bet_amount: f64,
+    placed_at: U64,
+    state_version: u32, // Version of the live state when the bet was placed
+    min_potential_winnings: Option<f64>,
+    min_odds: Option<f64>,
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of in-play betting
+
+    // Call function that allows a reporter to reopen betting on a match in progress
+    // In-play bets have their own maximum stake and wait out the acceptance delay, in nanoseconds, before they are priced
+    pub fn open_in_play(&mut self, match_id: String, max_stake: f64, acceptance_delay: U64, live_state: LiveState) {
+        self.assert_reporter();
+        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+
+        require!(current_match.in_play.as_ref().is_none_or(|in_play| !in_play.open), "That match is already open for in-play betting");
+        require!(current_match.proposed_result.is_none(), "A result has already been proposed for that match");
+        require!(!current_match.pricing_model().settles_at_finish() && !matches!(current_match.pricing, PricingKind::FixedOdds { .. }), "In-play betting needs a match priced from its pools");
+        require!(max_stake >= self.stake_limits.min_stake, "The maximum in-play stake can't be less than the minimum stake");
+
+        let promised_winnings: f64 = current_match.in_play.as_ref().map_or(0.0, |in_play| in_play.promised_winnings); // Keeps the bets from an earlier in-play phase
+        let pending_bets: Vec<PendingBet> = Vec::new();
+        let mut in_play: InPlay = InPlay{open: true, live_state: live_state.clone(), state_version: 0, updated_at: U64(0), red_pool: 0.0, blue_pool: 0.0, promised_winnings, max_stake, acceptance_delay, pending_bets};
+        current_match.reprice_in_play(&mut in_play, live_state);
+        current_match.in_play = Some(in_play);
+
+        self.in_progress_matches.insert(&match_id, &current_match); // Updates the match
+        log!("In-play betting is open for {}", match_id)
+    }
+
+    // Call function that allows a reporter to push the live state of a match, the live odds are reset from it
+    // Pending bets placed before the update are returned when they come to be priced
+    pub fn update_live_state(&mut self, match_id: String, live_state: LiveState) {
+        self.assert_reporter();
+        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let mut in_play: InPlay = current_match.in_play.take().unwrap_or_else(|| panic!("That match has no in-play betting"));
+
+        current_match.reprice_in_play(&mut in_play, live_state);
+        let version: u32 = in_play.state_version;
+        current_match.in_play = Some(in_play);
+
+        self.in_progress_matches.insert(&match_id, &current_match); // Updates the match
+        log!("The live state of {} has been updated to version {}", match_id, version)
+    }
+
+    // Call function that allows a reporter to stop taking in-play bets on a match, pending bets are returned
+    pub fn close_in_play(&mut self, match_id: String) {
+        self.assert_reporter();
+        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+
+        require!(current_match.in_play.as_ref().is_some_and(|in_play| in_play.open), "That match isn't open for in-play betting");
+        current_match.close_in_play();
+
+        self.in_progress_matches.insert(&match_id, &current_match); // Updates the match
+        log!("In-play betting is closed for {}", match_id)
+    }
+
+    // Call function that anyone can call to price the pending bets on a match that have waited out the acceptance delay
+    // A bet is returned if the live state changed while it was waiting, or it can no longer be accepted
+    pub fn accept_in_play_bets(&mut self, match_id: String) {
+        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let mut in_play: InPlay = current_match.in_play.take().unwrap_or_else(|| panic!("That match has no in-play betting"));
+
+        let now: u64 = env::block_timestamp();
+        let (due_bets, waiting_bets): (Vec<PendingBet>, Vec<PendingBet>) = in_play.pending_bets.drain(..).partition(|pending_bet| now >= pending_bet.placed_at.0 + in_play.acceptance_delay.0);
+        in_play.pending_bets = waiting_bets;
+
+        let mut accepted: u32 = 0;
+        for pending_bet in due_bets {
+            match self.in_play_bet_error(&current_match, &in_play, &pending_bet) {
+                Some(error) => {
+                    log!("A bet from {} has been returned: {}", pending_bet.bettor, error);
+                    transfer_usdc(pending_bet.bettor, pending_bet.bet_amount, "Return funds", near_sdk::Gas(30000000000000));
+                }
+                None => {
+                    self.accept_in_play_bet(&mut current_match, &mut in_play, pending_bet);
+                    accepted += 1;
+                }
+            }
+        }
+        current_match.in_play = Some(in_play);
+
+        self.in_progress_matches.insert(&match_id, &current_match); // Updates the match
+        log!("{} in-play bets have been accepted", accepted)
+    }
+
+    // View function that allows the user to view the in-play phase of a match in progress, including the live state and pending bets
+    pub fn view_in_play(&self, match_id: String) -> Option<InPlay> {
+        let current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        current_match.in_play
+    }
+
+    // View function that allows the user to view the current live odds of both alliances
+    pub fn view_live_odds(&self, match_id: String) -> Vec<f64> {
+        let current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let in_play: &InPlay = current_match.in_play.as_ref().unwrap_or_else(|| panic!("That match has no in-play betting"));
+        current_match.pricing_model().odds(&in_play.pools()).into_iter().map(round_odds).collect()
+    }
+}
+
+
+impl MatchList { // Helper functions for in-play betting
+
+    // Adds a bet to the pending bets of a match open for in-play betting, it is priced once the acceptance delay has passed
+    pub(crate) fn queue_in_play_bet(&self, current_match: &mut Match, bettor: AccountId, decision: AllianceColour, bet_amount: f64, min_potential_winnings: Option<f64>, min_odds: Option<f64>) {
+        require!(current_match.proposed_result.is_none(), "A result has already been proposed for that match"); // The result is public so the bet would be on a known winner
+        let in_play: &mut InPlay = current_match.in_play.as_mut().filter(|in_play| in_play.open).unwrap_or_else(|| panic!("That match isn't open for in-play betting"));
+
+        if let Some(error) = self.stake_limits.bet_size_error(bet_amount) {
+            panic!("{}", error)
+        }
+        require!(bet_amount <= in_play.max_stake, format!("The maximum in-play bet is {}", in_play.max_stake));
+
+        let placed_at: U64 = U64(env::block_timestamp());
+        let state_version: u32 = in_play.state_version;
+        in_play.pending_bets.push(PendingBet{bettor, decision, bet_amount, placed_at, state_version, min_potential_winnings, min_odds});
+        log!("Your in-play bet will be priced in {} nanoseconds if the live state doesn't change", in_play.acceptance_delay.0)
+    }
+
+    // Returns why a pending bet can't be accepted, or None if it can
+    fn in_play_bet_error(&self, current_match: &Match, in_play: &InPlay, pending_bet: &PendingBet) -> Option<String> {
+        if !in_play.open {
+            return Some("In-play betting has closed".to_string())
+        }
+        if pending_bet.state_version != in_play.state_version {
+            return Some("The live state changed while the bet was waiting".to_string())
+        }
+
+        let potential_winnings: f64 = current_match.pricing_model().winnings(&in_play.pools(), pending_bet.decision.index(), pending_bet.bet_amount);
+        if pending_bet.min_potential_winnings.is_some_and(|min| potential_winnings < min) || pending_bet.min_odds.is_some_and(|min| potential_winnings / pending_bet.bet_amount < min) {
+            return Some(format!("The potential winnings would have been {} which is worse than was accepted", potential_winnings))
+        }
+        if let Some(error) = self.stake_limit_error(current_match, Some(&pending_bet.bettor), pending_bet.decision, pending_bet.bet_amount, potential_winnings) {
+            return Some(error)
+        }
+
+        let promised_winnings: f64 = in_play.promised_winnings_after(pending_bet.decision, potential_winnings);
+        if self.bet_counter - in_play.promised_winnings.abs() + promised_winnings.abs() >= available_funds() {
+            return Some("We wouldn't definetly be able to pay out".to_string())
+        }
+        None
+    }
+
+    // Prices a pending bet on the live pools and adds it to the bets of the match, tagged as in-play
+    fn accept_in_play_bet(&mut self, current_match: &mut Match, in_play: &mut InPlay, pending_bet: PendingBet) {
+        let model: Box<dyn PricingModel> = current_match.pricing_model();
+        let mut pools: Vec<f64> = in_play.pools();
+        let potential_winnings: f64 = model.winnings(&pools, pending_bet.decision.index(), pending_bet.bet_amount);
+
+        self.bet_counter -= in_play.promised_winnings.abs(); // Takes off the absolute promised winnings as they will change
+        model.apply_bet(&mut pools, pending_bet.decision.index(), pending_bet.bet_amount, potential_winnings);
+        in_play.set_pools(&pools);
+        in_play.promised_winnings = in_play.promised_winnings_after(pending_bet.decision, potential_winnings);
+        self.bet_counter += in_play.promised_winnings.abs(); // Adds this back on with changed amount
+
+        self.record_daily_stake(&pending_bet.bettor, pending_bet.bet_amount);
+        let new_bet: Bet = Bet{bettor: pending_bet.bettor, decision: pending_bet.decision, bet_amount: pending_bet.bet_amount, potential_winnings, payed_out: PayedOut::YetToBePayed, odds_version: None, in_play: true};
+        current_match.bets.push(new_bet);
+    }
+}
+
+
+impl Match { // Helper functions for in-play matches
+
+    // Resets the live pools of a match from a new live state
+    fn reprice_in_play(&self, in_play: &mut InPlay, live_state: LiveState) {
+        require!(live_state.red_probability > 0.0 && live_state.red_probability < 1.0, "The red probability must be between 0 and 1");
+        let pools: Vec<f64> = self.pricing_model().seed_pools(&[live_state.red_probability, 1.0 - live_state.red_probability], self.virtual_liquidity);
+        in_play.set_pools(&pools);
+        in_play.live_state = live_state;
+        in_play.state_version += 1;
+        in_play.updated_at = U64(env::block_timestamp());
+    }
+
+    // Stops taking in-play bets and returns the pending bets, the accepted bets are settled with the match
+    pub(crate) fn close_in_play(&mut self) {
+        if let Some(in_play) = self.in_play.as_mut() {
+            in_play.open = false;
+            for pending_bet in in_play.pending_bets.drain(..) {
+                transfer_usdc(pending_bet.bettor, pending_bet.bet_amount, "Return funds", near_sdk::Gas(30000000000000));
+            }
+        }
+    }
+
+    // Returns the absolute promised winnings of the in-play bets, which are held in the bet_counter until the match is settled
+    pub(crate) fn in_play_promised_winnings(&self) -> f64 {
+        self.in_play.as_ref().map_or(0.0, |in_play| in_play.promised_winnings.abs())
+    }
+}
+
+
+impl InPlay { // Helper functions for the in-play phase
+
+    // Returns the live pools of both alliances in the order used by the pricing models
+    fn pools(&self) -> Vec<f64> {
+        vec![self.red_pool, self.blue_pool]
+    }
+
+    // Updates the live pools of both alliances from the order used by the pricing models
+    fn set_pools(&mut self, pools: &[f64]) {
+        self.red_pool = pools[AllianceColour::Red.index()];
+        self.blue_pool = pools[AllianceColour::Blue.index()];
+    }
+
+    // Returns what the promised_winnings of the in-play bets would be after a bet
+    fn promised_winnings_after(&self, decision: AllianceColour, potential_winnings: f64) -> f64 {
+        match decision {
+            AllianceColour::Red => self.promised_winnings + potential_winnings,
+            AllianceColour::Blue => self.promised_winnings - potential_winnings,
+        }
+    }
+}
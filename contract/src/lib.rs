@@ -1,20 +1,68 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::{env, AccountId, Promise, PromiseResult, near_bindgen, require, log, ONE_YOCTO};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U64;
+use near_sdk::{env, AccountId, Promise, near_bindgen, require, log, ONE_YOCTO};
 use serde_json::json;
 
+mod config;
+mod events;
+mod exchange;
+mod fixed_odds;
+mod futures;
+mod history;
+mod in_play;
+mod limits;
+mod markets;
+mod metadata;
+mod odds;
+mod oracle;
+mod parlay;
+mod pricing;
+mod quotes;
+mod schedule;
+mod settlement;
+mod teams;
+
+use crate::config::Config;
+use crate::events::Event;
+use crate::exchange::{MatchedBet, Order, OrderSide};
+use crate::history::OddsHistory;
+use crate::in_play::InPlay;
+use crate::limits::{DailyStake, StakeLimits};
+use crate::markets::{Market, MarketBetData};
+use crate::metadata::MatchMetadata;
+use crate::oracle::{DisputeData, ProposedResult};
+use crate::parlay::{LegData, Parlay};
+use crate::pricing::{PricingKind, PricingModel};
+use crate::schedule::MatchSpec;
+use crate::teams::Team;
+
 const ONE_USDC: f64 = 1000000000000000000000000.0;
-const USDC_CONTRACT: &'static str = "cusd.fakes.testnet";
+const USDC_CONTRACT: &str = "cusd.fakes.testnet";
+const MAX_ALLIANCE_SIZE: usize = 2; // VRC alliances have two teams, VEX U alliances have one
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
-struct MatchList {
+pub struct MatchList {
     future_matches: UnorderedMap<String, Match>, // Map of future matches, match_id is the key
     in_progress_matches: UnorderedMap<String, Match>, // Map of in progress matches
     complete_matches: UnorderedMap<String, Match>, // Map of completed matches
     error_matches: UnorderedMap<String, Match>, // Map of matches that an error has occured e.g. player dropped out
     bet_counter: f64, // Created by summing up all the absolute values of potential_winnings over all games
+    teams: UnorderedMap<String, Team>, // Registry of teams, team number is the key
+    reporters: UnorderedSet<AccountId>, // Accounts that are allowed to report team ratings
+    arbiters: UnorderedSet<AccountId>, // Accounts that are allowed to resolve disputed results
+    config: Config, // Defaults used when creating a match
+    stake_limits: StakeLimits, // Limits on how much can be bet
+    daily_stakes: LookupMap<AccountId, DailyStake>, // How much each account has bet today
+    parlays: UnorderedMap<u64, Parlay>, // Map of parlays, parlay_id is the key
+    match_parlays: LookupMap<String, Vec<u64>>, // The parlays with an unsettled leg on each match, match_id is the key
+    next_parlay_id: u64,
+    markets: UnorderedMap<u64, Market>, // Map of side markets on matches, market_id is the key
+    match_markets: LookupMap<String, Vec<u64>>, // The open markets on each match, match_id is the key
+    next_market_id: u64,
+    events: UnorderedMap<String, Event>, // Map of events, the RobotEvents SKU is the key
 }
 
 // Struct that holds the details of a match and the bets made in a match
@@ -22,24 +70,92 @@ struct MatchList {
 #[serde(crate = "near_sdk::serde")]
 struct Match { 
     bets: Vec<Bet>, // List of bets made on a match
-    team_1: String,
-    team_2: String,
-    team_1_total_bets: f64, 
-    team_2_total_bets: f64, 
-    promised_winnings: f64, 
-    winner: Option<String>,
+    red_alliance: Alliance,
+    blue_alliance: Alliance,
+    event: Option<String>, // SKU of the event the match is played at
+    division: String, // Event division the match is played in e.g. "Science"
+    round: u32, // Round of the match, qualifications are round 1
+    match_number: u32, // Match number within the round
+    metadata: MatchMetadata, // Round type, field, stream and start time shown to bettors
+    red_total_bets: f64, // Pool of the pricing model for the red alliance, for most models this is the total bets on red
+    blue_total_bets: f64, // Pool of the pricing model for the blue alliance
+    promised_winnings: f64, // Positive when more is promised to red bettors, negative when more is promised to blue bettors
+    house_margin: f64, // Overround added to the odds of this match, or the rake of a pari-mutuel pool
+    virtual_liquidity: f64, // Amount the bets pools of this match were seeded with
+    pricing: PricingKind, // Pricing model used for bets on this match
+    orders: Vec<Order>, // Order book of an exchange match, order_id is the index
+    matched_bets: Vec<MatchedBet>, // Bets between users on an exchange match
+    odds_history: OddsHistory, // Snapshots of how the odds have moved while betting was open
+    in_play: Option<InPlay>, // Live state and pricing of the match once it is in progress, None if it was never opened for in-play betting
+    winner: Option<AllianceColour>,
+    red_score: Option<u32>, // Final score of the red alliance, recorded when the match finishes
+    blue_score: Option<u32>,
+    proposed_result: Option<ProposedResult>, // Result waiting out the challenge window, or a dispute
     match_state: MatchState,
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+struct Alliance { // Struct that holds the teams playing together on one side of a match
+    colour: AllianceColour,
+    teams: Vec<String>, // Team numbers e.g. "1234A"
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 struct Bet {  // Struct that holds the details of a single bet 
     bettor: AccountId,
-    decision: String,
+    decision: AllianceColour,
     bet_amount: f64,
     potential_winnings: f64, 
     payed_out: PayedOut,
+    odds_version: Option<u32>, // Version of the fixed odds the bet was placed at
+    in_play: bool, // Whether the bet was placed while the match was in progress
+}
+
+// Tuple returned by view_bets of the bet_id, bettor, alliance, bet amount, potential winnings, whether it has been payed and whether it was placed in-play
+type BetView = (u64, String, AllianceColour, f64, f64, PayedOut, bool);
+
+// Struct returned by the match view functions
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchView {
+    match_id: String,
+    red_teams: Vec<String>,
+    red_odds: f64,
+    blue_teams: Vec<String>,
+    blue_odds: f64,
+    event: Option<String>,
+    division: String,
+    round: u32,
+    match_number: u32,
+    metadata: MatchMetadata,
+    house_margin: f64,
+    virtual_liquidity: f64,
+    pricing: PricingKind,
+    winner: Option<AllianceColour>,
+    red_score: Option<u32>,
+    blue_score: Option<u32>,
+    match_state: MatchState,
 }
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AllianceColour {
+    Red,
+    Blue,
+}
+
+impl AllianceColour {
+    // Returns the position of the alliance in the pools passed to the pricing models
+    fn index(self) -> usize {
+        match self {
+            AllianceColour::Red => 0,
+            AllianceColour::Blue => 1,
+        }
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub enum MatchState {
@@ -56,6 +172,8 @@ pub enum PayedOut {
     Payed,
     ReturnPay,
     NotPayed,
+    Cancelled, // Cancelled by the bettor before betting closed
+    CashedOut, // Sold back to the house before betting closed
 }
 
 // Default implementation for MatchList that creates new maps and bet_counter
@@ -65,16 +183,86 @@ impl Default for MatchList {
         in_progress_matches: UnorderedMap::new(b"p"), 
         complete_matches: UnorderedMap::new(b"c"), 
         error_matches: UnorderedMap::new(b"e"), 
-        bet_counter: 0.0}
+        bet_counter: 0.0,
+        teams: UnorderedMap::new(b"t"),
+        reporters: UnorderedSet::new(b"r"),
+        arbiters: UnorderedSet::new(b"b"),
+        config: Config::default(),
+        stake_limits: StakeLimits::default(),
+        daily_stakes: LookupMap::new(b"d"),
+        parlays: UnorderedMap::new(b"a"),
+        match_parlays: LookupMap::new(b"l"),
+        next_parlay_id: 0,
+        markets: UnorderedMap::new(b"m"),
+        match_markets: LookupMap::new(b"k"),
+        next_market_id: 0,
+        events: UnorderedMap::new(b"v")}
     }
   }
 
 
+impl MatchList { // Helper functions for MatchList
+
+    // Returns a match from whichever map it is in
+    fn find_match(&self, match_id: &String) -> Option<Match> {
+        self.future_matches.get(match_id)
+            .or_else(|| self.in_progress_matches.get(match_id))
+            .or_else(|| self.complete_matches.get(match_id))
+            .or_else(|| self.error_matches.get(match_id))
+    }
+
+    // Returns why a match can't be created from this spec, or None if it can
+    fn create_match_error(&self, spec: &MatchSpec) -> Option<String> {
+        let MatchSpec{red_teams, blue_teams, division, in_odds_red, in_odds_blue, house_margin, virtual_liquidity, pricing, event, metadata, ..} = spec;
+        if self.find_match(&spec.match_id()).is_some() { // Stops a match from being overwritten
+            return Some("A match already exists with that match_id".to_string())
+        }
+        if red_teams.is_empty() || red_teams.len() > MAX_ALLIANCE_SIZE { // Checks the alliances are a valid size
+            return Some("The red alliance must have one or two teams".to_string())
+        }
+        if blue_teams.is_empty() || blue_teams.len() > MAX_ALLIANCE_SIZE {
+            return Some("The blue alliance must have one or two teams".to_string())
+        }
+        if red_teams.iter().any(|team| blue_teams.contains(team)) { // Checks no team is playing against itself
+            return Some("A team can't be on both alliances".to_string())
+        }
+        if let Some(error) = self.config.overridden(*house_margin, *virtual_liquidity).error() { // Checks the overrides are in bounds
+            return Some(error)
+        }
+        if let Some(error) = pricing.as_ref().and_then(|pricing| pricing.error(2)) {
+            return Some(error)
+        }
+        if let Some(error) = metadata.as_ref().and_then(|metadata| metadata.error()) {
+            return Some(error)
+        }
+        match (*in_odds_red, *in_odds_blue) {
+            (Some(in_odds_red), Some(in_odds_blue)) => {
+                if ![in_odds_red, in_odds_blue].iter().all(|odds| odds.is_finite() && *odds > 1.0) {
+                    return Some("The odds of both alliances must be greater than 1".to_string())
+                }
+            }
+            (None, None) => { // The odds will come from the team registry
+                if let Some(team_number) = red_teams.iter().chain(blue_teams.iter()).find(|team_number| self.teams.get(team_number).is_none()) {
+                    return Some(format!("No team is registered with team number: {}", team_number))
+                }
+                let (prob_red, prob_blue): (f64, f64) = self.rating_probabilities(red_teams, blue_teams);
+                if !(prob_red > 0.0 && prob_blue > 0.0) { // The ratings are so far apart that one alliance has no chance
+                    return Some("The ratings of the alliances are too far apart to price the match".to_string())
+                }
+            }
+            _ => return Some("Either input odds for both alliances or for neither".to_string()),
+        }
+        event.as_ref().and_then(|sku| self.event_match_error(sku, division))
+    }
+}
+
+
 #[near_bindgen]
 impl MatchList { // Implementation of MatchList
 
-    // Call function that allows the user to make a bet on a future match on either team 1 or team 2 in USDC
+    // Call function that allows the user to make a bet on a future match on either the red or blue alliance in USDC
     pub fn ft_on_transfer(&mut self, sender_id: String, amount: String, msg: String) -> String {
+        require!(env::predecessor_account_id().as_str() == USDC_CONTRACT, "Only USDC can be used"); // Stops anyone calling this directly without sending any USDC
         let bettor: AccountId = sender_id.try_into().unwrap();
         let bet_amount: f64 = amount.parse::<f64>().unwrap();
 
@@ -83,111 +271,215 @@ impl MatchList { // Implementation of MatchList
         #[serde(crate = "near_sdk::serde")]
         struct ParsedData {
             match_id: String,
-            decision: String,
+            decision: AllianceColour,
+            odds_version: Option<u32>, // Version of the fixed odds the bettor was shown
+            side: Option<OrderSide>, // Back or lay, only for exchange matches
+            odds: Option<f64>, // Odds of the order, only for exchange matches
+            min_potential_winnings: Option<f64>, // Smallest potential winnings the bettor will accept
+            min_odds: Option<f64>, // Smallest odds, potential winnings divided by the bet amount, the bettor will accept
+            deadline: Option<U64>, // Block timestamp in nanoseconds after which the bet is no longer wanted
+        }
+
+        // Struct to parse a parlay into
+        #[derive(Deserialize)]
+        #[serde(crate = "near_sdk::serde")]
+        struct ParsedParlay {
+            legs: Vec<LegData>, // One pick on each match in the parlay
+        }
+
+        // Parlays are placed across several matches instead of on one
+        if let Ok(parsed_parlay) = serde_json::from_str::<ParsedParlay>(&msg) {
+            self.place_parlay(bettor, bet_amount / ONE_USDC, parsed_parlay.legs);
+            return "0".to_string()
+        }
+
+        // Disputes of a proposed result send the bond instead of a bet
+        if let Ok(dispute) = serde_json::from_str::<DisputeData>(&msg) {
+            self.dispute_result(bettor, bet_amount / ONE_USDC, dispute);
+            return "0".to_string()
+        }
+
+        // Bets on a side market of a match say which market and outcome instead of an alliance
+        if let Ok(market_bet) = serde_json::from_str::<MarketBetData>(&msg) {
+            return if self.place_market_bet(bettor, bet_amount / ONE_USDC, market_bet) { "0".to_string() } else { amount }
         }
 
         // Parse msg from json to varaibles
         let parsed_data: ParsedData = serde_json::from_str(&msg).unwrap_or_else(|err: serde_json::Error| panic!("Invalid json {}", err));
         let match_id: String = parsed_data.match_id;
-        let decision: String = parsed_data.decision;
+        let decision: AllianceColour = parsed_data.decision;
+        let shown_odds_version: Option<u32> = parsed_data.odds_version;
 
         let bet_amount: f64 = bet_amount / ONE_USDC; // Gets the amount attatched to the bet
 
+        if let Some(deadline) = parsed_data.deadline { // Returns the whole amount as unused if the bet arrived too late
+            if env::block_timestamp() > deadline.0 {
+                log!("The deadline for this bet has passed so it has been returned");
+                return amount
+            }
+        }
+
+        // Bets on a match in progress wait out the in-play acceptance delay before they are priced
+        if let Some(mut live_match) = self.in_progress_matches.get(&match_id) {
+            require!(parsed_data.side.is_none() && parsed_data.odds.is_none(), "Only exchange matches take orders");
+            self.queue_in_play_bet(&mut live_match, bettor, decision, bet_amount, parsed_data.min_potential_winnings, parsed_data.min_odds);
+            self.in_progress_matches.insert(&match_id, &live_match); // Updates the match
+            return "0".to_string()
+        }
+
         // Finds the relevent match
         let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id));
 
         require!(current_match.match_state == MatchState::Future, "The game is complete or in progress"); // Match state isn't Future
+        if current_match.pricing == PricingKind::Exchange { // Exchange bets go on the order book instead of against the house
+            if let Some(error) = self.stake_limit_error(&current_match, Some(&bettor), decision, bet_amount, 0.0) { // The house promises nothing on an exchange match
+                panic!("{}", error)
+            }
+            let side: OrderSide = parsed_data.side.unwrap_or_else(|| panic!("An exchange bet needs a side"));
+            let odds: f64 = parsed_data.odds.unwrap_or_else(|| panic!("An exchange bet needs odds"));
+            self.record_daily_stake(&bettor, bet_amount);
+            current_match.place_order(bettor, decision, side, odds, bet_amount);
+            current_match.record_odds();
+            self.future_matches.insert(&match_id, &current_match); // Updates the match
+            return "0".to_string()
+        }
+        require!(parsed_data.side.is_none() && parsed_data.odds.is_none(), "Only exchange matches take orders");
+
+        let odds_version: Option<u32> = current_match.pricing.odds_version();
+        require!(shown_odds_version.is_none() || shown_odds_version == odds_version, "The odds have changed since they were shown to you"); // Fixed odds were updated before the bet arrived
         
         // Calculates how much will be payed out, will change as odds change with amount betted
-        let mut potential_winnings: f64 = 0.0;
-        if decision == current_match.team_1 { // If they have picked team 1
-            potential_winnings = find_winnings(current_match.team_1_total_bets, current_match.team_2_total_bets, bet_amount);
-        } else if decision == current_match.team_2 { // If they have picked team 2
-            potential_winnings = find_winnings(current_match.team_2_total_bets, current_match.team_1_total_bets, bet_amount);
-        } else { // If not inputted correct team
-            panic!("That is not a valid team")
+        let model: Box<dyn PricingModel> = current_match.pricing_model();
+        let mut pools: Vec<f64> = current_match.pools();
+        let potential_winnings: f64 = model.winnings(&pools, decision.index(), bet_amount);
+        current_match.check_liability_limit(decision, bet_amount, potential_winnings); // Stops accepting bets on an outcome once the house could lose too much on it
+        if let Some(error) = self.stake_limit_error(&current_match, Some(&bettor), decision, bet_amount, potential_winnings) {
+            panic!("{}", error)
+        }
+
+        // Returns the whole amount as unused if the odds have moved against the bettor since they were shown
+        if parsed_data.min_potential_winnings.is_some_and(|min| potential_winnings < min) || parsed_data.min_odds.is_some_and(|min| potential_winnings / bet_amount < min) {
+            log!("The potential winnings would have been {} which is worse than you accepted so the bet has been returned", potential_winnings);
+            return amount
         }
 
+        let promised_winnings: f64 = current_match.promised_winnings_after(model.as_ref(), decision, potential_winnings);
         self.bet_counter -= current_match.promised_winnings.abs(); // Takes off the absolute promised winnings as they will change
 
-        // Adds the bet to the total bets and changes the promised_winnings for that match
-        if decision == current_match.team_1 { // If they have picked team 1
-            current_match.team_1_total_bets += bet_amount;
-            current_match.promised_winnings += potential_winnings;
-        } else if decision == current_match.team_2 { // If they have picked team 2
-            current_match.team_2_total_bets += bet_amount;
-            current_match.promised_winnings -= potential_winnings;
-        }
-        
+        // Adds the bet to the pools and changes the promised_winnings for that match
+        model.apply_bet(&mut pools, decision.index(), bet_amount, potential_winnings);
+        current_match.set_pools(&pools);
+        current_match.promised_winnings = promised_winnings;
 
         self.bet_counter += current_match.promised_winnings.abs(); // Adds this back on with changed amount
-        if self.bet_counter >= (env::account_balance() / ONE_USDC as u128) as f64 { // If the bet counter is larger than amount available in the contract then panics
+        if self.bet_counter >= available_funds() { // If the bet counter is larger than amount available in the contract then panics
             panic!("Sorry you can't make a bet as we wouldn't definetly be able to pay out")
         }
 
+        self.record_daily_stake(&bettor, bet_amount);
+
         let payed_out: PayedOut = PayedOut::YetToBePayed; 
         // Potential winnings are stored in yoctoNEAR
-        let new_bet: Bet = Bet{bettor, decision: decision.clone(), bet_amount: bet_amount.clone(), potential_winnings: potential_winnings.clone(), payed_out: payed_out.clone()}; // Creates a new bet with the fields filled in
+        let new_bet: Bet = Bet{bettor, decision, bet_amount, potential_winnings, payed_out, odds_version, in_play: false}; // Creates a new bet with the fields filled in
         current_match.bets.push(new_bet); // Pushes the new bet to the bets list for that match
+        current_match.record_odds();
         self.future_matches.insert(&match_id, &current_match); // Updates the match
-        log!("You have made a bet on {}, with ${} , at odds {}, and potential winnings {}", decision, bet_amount, potential_winnings / bet_amount, potential_winnings);
+        log!("You have made a bet on {:?}, with ${} , at odds {}, and potential winnings {}", decision, bet_amount, potential_winnings / bet_amount, potential_winnings);
 
-        return "0".to_string()
+        "0".to_string()
+    }
+
+    // Call function that allows a bettor to cancel one of their bets while the match is still in the future
+    // The stake is returned minus the cancellation fee, bet_id is the position of the bet in the match's bets
+    // When the house promises the winnings a bet can only be cancelled until another bet moves the pools, after that it is cashed out at its current value
+    pub fn cancel_bet(&mut self, match_id: String, bet_id: u64) {
+        let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let bet_index: usize = bet_id as usize;
+        let bet: &Bet = current_match.bets.get(bet_index).unwrap_or_else(|| panic!("No bet exists with bet_id: {}", bet_id));
+
+        require!(bet.bettor == env::predecessor_account_id(), "You can only cancel your own bets");
+        require!(current_match.match_state == MatchState::Future, "Betting has closed for that match");
+        require!(bet.payed_out == PayedOut::YetToBePayed, "That bet has already been settled or cancelled");
+        if !current_match.pricing_model().settles_at_finish() { // Otherwise a bet could be hedged at the moved price and the original taken back at its stake
+            require!(bet_index + 1 == current_match.bets.len(), "Bets have been placed since, cash out the bet instead");
+        }
+
+        let bettor: AccountId = bet.bettor.clone();
+        let refund: f64 = bet.bet_amount * (1.0 - self.config.cancellation_fee); // The house keeps the fee
+
+        self.bet_counter -= current_match.promised_winnings.abs(); // Takes off the absolute promised winnings as they will change
+        current_match.remove_bet(bet_index, None);
+        self.bet_counter += current_match.promised_winnings.abs(); // Adds this back on with changed amount
+        current_match.bets[bet_index].payed_out = PayedOut::Cancelled;
+        current_match.record_odds();
+
+        transfer_usdc(bettor, refund, "Cancelled bet", near_sdk::Gas(30000000000000));
+        self.future_matches.insert(&match_id, &current_match); // Updates the match
+        log!("Bet {} has been cancelled and {} has been returned", bet_id, refund)
+    }
+
+    // Call function that allows a bettor to sell one of their bets back to the house at its current value while the match is still in the future
+    pub fn cash_out(&mut self, match_id: String, bet_id: u64) {
+        let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let bet_index: usize = bet_id as usize;
+        let bet: &Bet = current_match.bets.get(bet_index).unwrap_or_else(|| panic!("No bet exists with bet_id: {}", bet_id));
+
+        require!(bet.bettor == env::predecessor_account_id(), "You can only cash out your own bets");
+        require!(current_match.match_state == MatchState::Future, "Betting has closed for that match");
+        require!(bet.payed_out == PayedOut::YetToBePayed, "That bet has already been settled or cancelled");
+
+        let bettor: AccountId = bet.bettor.clone();
+        let sale_stake: f64 = current_match.sale_stake(bet_index);
+        let value: f64 = current_match.cash_out_value(bet_index);
+
+        self.bet_counter -= current_match.promised_winnings.abs(); // Takes off the absolute promised winnings as they will change
+        current_match.remove_bet(bet_index, Some(sale_stake)); // The house no longer owes the winnings
+        self.bet_counter += current_match.promised_winnings.abs(); // Adds this back on with changed amount
+        current_match.bets[bet_index].payed_out = PayedOut::CashedOut;
+        current_match.record_odds();
+
+        transfer_usdc(bettor, value, "Cash out", near_sdk::Gas(30000000000000));
+        self.future_matches.insert(&match_id, &current_match); // Updates the match
+        log!("Bet {} has been cashed out for {}", bet_id, value)
+    }
+
+    // View function that allows the user to view how much a bet could currently be cashed out for
+    pub fn view_cash_out_value(&self, match_id: String, bet_id: u64) -> f64 {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let bet: &Bet = current_match.bets.get(bet_id as usize).unwrap_or_else(|| panic!("No bet exists with bet_id: {}", bet_id));
+        require!(bet.payed_out == PayedOut::YetToBePayed, "That bet has already been settled or cancelled");
+        current_match.cash_out_value(bet_id as usize)
     }
 
     // View function that allows the user to view all future matches
-    pub fn view_future_matches(&self, match_id: String) -> Vec<(String, String, f64, String, f64, Option<String>, MatchState)> {
-        let mut match_list: Vec<(String, String, f64, String, f64, Option<String>, MatchState)> = Vec::new(); // Creates a new empty list where the required values will get added to 
+    pub fn view_future_matches(&self, match_id: String) -> Vec<MatchView> {
+        let mut match_list: Vec<MatchView> = Vec::new(); // Creates a new empty list where the required values will get added to 
 
         if match_id == "all" {
-            let keys: Vec<String> = (self.future_matches.keys_as_vector()).to_vec(); // Converts all the matches keys into a vector 
-            let values: Vec<Match> = (self.future_matches.values_as_vector()).to_vec(); // Converts all matches values into a vector
-            for i in 0..self.future_matches.len() { // Loops the length of the list
-                let i: usize = i.try_into().unwrap(); // Converts i to usize to index properly
-                let key: String = keys[i].to_string(); // Converts values to a form that can be used
-                let team_name_1: String = (values[i].team_1).to_string();
-                let team_name_2: String = (values[i].team_2).to_string();
-                let winner: Option<String> = values[i].winner.clone();
-                let match_state: MatchState = values[i].match_state;
-                let odds: (f64, f64) = find_starting_odds(values[i].team_1_total_bets, values[i].team_2_total_bets); // Gets the odds for the game
-                let individual_match: (String, String, f64, String, f64, Option<String>, MatchState) = (key, team_name_1, odds.0, team_name_2, odds.1, winner, match_state); // Creates a tuple of infomation
-                match_list.push(individual_match) // Pushes this tuple to the list
+            for (key, current_match) in self.future_matches.iter() { // Loops through all the future matches
+                match_list.push(current_match.view(key)) // Pushes the view of the match to the list
             }
         } else {
             let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
-            let team_name_1: String = (current_match.team_1).to_string();
-            let team_name_2: String = (current_match.team_2).to_string();
-            let winner: Option<String> = current_match.winner;
-            let match_state: MatchState = current_match.match_state;
-            let odds: (f64, f64) = find_starting_odds(current_match.team_1_total_bets, current_match.team_2_total_bets); // Gets the odds for the game
-            let individual_match: (String, String, f64, String, f64, Option<String>, MatchState) = (match_id, team_name_1, odds.0, team_name_2, odds.1, winner, match_state); // Creates a tuple of infomation
-            match_list.push(individual_match) // Pushes this tuple to the list
+            match_list.push(current_match.view(match_id)) // Pushes the view of the match to the list
         }
 
         match_list // Returns the list
     }
 
-    // Private call function that allows the contract account to create a new match, need to input teams, odds and the date of the match
-    #[private]
-    pub fn create_match(&mut self, team_1: String, team_2: String, in_odds_1: f64, in_odds_2: f64, date: String) {
-        let match_id: String = format!("{}-{}-{}", team_1, team_2, date); // The match_id is formed from the team names and the date
-
-        // Creates inital bets pool inline with odds
-        let in_prob_1: f64 = 1.0 / in_odds_1; // Changes initial decimal odds to initial probability
-        let in_prob_2: f64 = 1.0 / in_odds_2;
-        let divider: f64 = in_prob_1 + in_prob_2; // Creates the divider by adding implied odds
-        let actual_prob_1: f64 = in_prob_1 / divider; // Divides initial probability to give actual probability
-        let actual_prob_2: f64 = in_prob_2 / divider;
-        let team_1_total_bets: f64 = (actual_prob_1 * 1000.0).round(); // Sets the initial bets and rounds, multiplies by weighting of 1000
-        let team_2_total_bets: f64 = (actual_prob_2 * 1000.0).round();
-
-        let bets: Vec<Bet> = Vec::new(); // Creates a new empty bets list that holds all the bets
-        let winner: Option<String> = None;
-        let match_state: MatchState = MatchState::Future;
-        let promised_winnings: f64 = 0.0;
-        let new_match: Match = Match{bets, team_1, team_2, team_1_total_bets, team_2_total_bets, promised_winnings, winner, match_state}; // Creates a new_match using the Match struct
-        self.future_matches.insert(&match_id, &new_match); // Adds this new_match to the future_matches map
-        log!("A new match has been added with ID {}", match_id)
+    // View function that allows the user to view every match, in any state, that a team is playing in
+    pub fn view_team_matches(&self, team_number: String) -> Vec<MatchView> {
+        let mut match_list: Vec<MatchView> = Vec::new(); // Creates a new empty list where the required values will get added to 
+
+        for matches in [&self.future_matches, &self.in_progress_matches, &self.complete_matches, &self.error_matches] { // Loops through each of the match maps
+            for (key, current_match) in matches.iter() {
+                if current_match.alliance_of(&team_number).is_some() { // Only adds matches the team is playing in
+                    match_list.push(current_match.view(key))
+                }
+            }
+        }
+
+        match_list // Returns the list
     }
 
 
@@ -199,82 +491,64 @@ impl MatchList { // Implementation of MatchList
         require!(current_match.match_state == MatchState::Future, "That game is already complete or in progress"); // Checks that the game has not already been ended
 
         current_match.match_state = MatchState::InProgress;
-        self.bet_counter -= current_match.promised_winnings.abs(); // Removes the promised winnings from the bet_counter
+        current_match.return_open_orders(); // Unmatched exchange orders can no longer be matched
+        // The promised winnings stay in the bet_counter until the result is recorded, which can take the whole challenge window or a dispute
         self.in_progress_matches.insert(&match_id, &current_match); // Inserts the match into in_progress_matches
         self.future_matches.remove(&match_id); // Removes the match from future_matches
     }
 
 
-    // Private call function that allows the contract account to finish a match, need to input the winning team
+    // Private call function that allows the contract account to finish a match, need to input the final score of each alliance
+    // The alliance with the higher score wins, the bets on a tied match are pushed and their stakes returned
+    // This is an emergency override of the result oracle, the result is final straight away with no challenge window or dispute
+    // Results should normally go through propose_result and finalize_result, and a match with a proposed result can't be finished here
     #[private]
-    pub fn finish_match(&mut self, match_id: String, winning_team: String) {
-        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
-        
-        require!(current_match.match_state == MatchState::InProgress, "That game is already complete or in the future"); // Checks that the game has not already been ended
-        require!(winning_team == current_match.team_1 || winning_team == current_match.team_2, "That is not a valid team"); // Checks valid winner input 
-
-        for i in 0..current_match.bets.len() { // Loops through all bets
-            if current_match.bets[i].payed_out == PayedOut::YetToBePayed { // Checks not already payed out and they bet on the winner
-                if current_match.bets[i].decision == winning_team { // Checks they bet on the winner
-                    
-                    let winner: AccountId = current_match.bets[i].bettor.clone(); // Gets the account Id of each winner
-                    let winnings: f64 = current_match.bets[i].potential_winnings * ONE_USDC; // Gets the amount they win
-                    let args: Vec<u8> = json!({
-                        "receiver_id": winner,
-                        "amount": winnings.to_string(),
-                        "memo": "Winnings",
-                    }).to_string().into_bytes();
-                    Promise::new(USDC_CONTRACT.parse().unwrap()).function_call("ft_transfer".to_string(), args, ONE_YOCTO, near_sdk::Gas(100000000000000));
-                    //Change to ft_transfer_call
-
-                    current_match.bets[i].payed_out = PayedOut::Payed;
-                }
-                
-            }
-        } 
-
-        current_match.winner = Some(winning_team); // Sets the winning team
-        current_match.match_state = MatchState::Complete;
-        self.complete_matches.insert(&match_id, &current_match); // Inserts the match into the complete_matches
-        self.in_progress_matches.remove(&match_id); // Removes the match from in_progress_matches
-        log!("The match is now complete")
+    pub fn finish_match(&mut self, match_id: String, red_score: u32, blue_score: u32) {
+        if let Some(error) = self.result_error(&match_id) {
+            panic!("{}", error)
+        }
+        self.record_result(&match_id, red_score, blue_score);
+
+        let mut current_match: Match = self.complete_matches.get(&match_id).unwrap();
+        let (_, payed): (usize, f64) = current_match.pay_out_bets(usize::MAX, None); // Pays out every bet straight away
+        self.settle_side_bets(&match_id, &current_match, usize::MAX, None);
+        self.bet_counter -= payed;
+        self.complete_matches.insert(&match_id, &current_match); // Updates the match
+        log!("The match is now complete, its result was overridden without a challenge window")
     }
 
 
     // Private call functio nthat allows the contract account to return funds to the bettors if a match was cancelled
     #[private]
     pub fn return_funds(&mut self, match_id: String, state: MatchState) {
-        let mut current_match: Option<Match> = None;
-
-        match state {
+        let current_match: Option<Match> = match state {
             MatchState::Future => {
-                current_match = Some(self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id))); // Finds the desired match, panics if doesn't find the match
+                Some(self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id))) // Finds the desired match, panics if doesn't find the match
             }
             MatchState::InProgress => {
-                current_match = Some(self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id))); // Finds the desired match, panics if doesn't find the match
+                Some(self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id))) // Finds the desired match, panics if doesn't find the match
             }
             _ => panic!("That is not a valid state"),
-        }
+        };
 
         match current_match { Some(mut x) => { // If there is a match
             for i in 0..x.bets.len() { // Loops through all bets
                 if x.bets[i].payed_out == PayedOut::YetToBePayed { // Checks not already payed out and they bet on the winner
                     // Payout this person (convert to balance)
                     let account: AccountId = x.bets[i].bettor.clone();
-                    let returns: f64 = x.bets[i].bet_amount * ONE_USDC;
-
-                    let args: Vec<u8> = json!({
-                        "receiver_id": account,
-                        "amount": returns.to_string(),
-                        "memo": "Return funds",
-                    }).to_string().into_bytes();
-                    Promise::new(USDC_CONTRACT.parse().unwrap()).function_call("ft_transfer".to_string(), args, ONE_YOCTO, near_sdk::Gas(30000000000000));
+                    let returns: f64 = x.bets[i].bet_amount;
+                    transfer_usdc(account, returns, "Return funds", near_sdk::Gas(30000000000000));
 
                     //Extra checks
                     //Update bet payed out for each individual sequencially not at end as one might be payed out but not others
                     x.bets[i].payed_out = PayedOut::ReturnPay;       
                 }
             } 
+            x.close_in_play(); // Returns the in-play bets that were never priced
+            x.return_dispute_bond(); // Returns the bond of any dispute of the result
+            x.return_exchange_funds(); // Returns the escrow of exchange orders and bets
+            self.settle_parlay_legs(&match_id, None, usize::MAX, None); // Voids the legs of any parlays on this match
+            self.settle_match_markets(&match_id, None, usize::MAX, None); // Returns the bets on the side markets of this match
 
             log!("Return pay has been issued");
         
@@ -287,6 +561,7 @@ impl MatchList { // Implementation of MatchList
                     self.future_matches.remove(&match_id); // Removes the match from future_matches
                 }
                 MatchState::InProgress => {
+                    self.bet_counter -= x.promised_winnings.abs() + x.in_play_promised_winnings(); // Removes the promised winnings of the bets before and during the match from the bet_counter
                     self.in_progress_matches.remove(&match_id); // Removes the match from in_progress_matches
                 }
                 _ => panic!("That is not a valid state")
@@ -299,20 +574,20 @@ impl MatchList { // Implementation of MatchList
 
 
     // View function that allows the user to view the bets for a single match
-    // Input either the bet ID to view a single bet or "all" to view all bets for that match
-    pub fn view_bets(&self, match_id: String, name: String) -> Vec<(String, String, f64, f64, PayedOut)> {
-        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id));  // Finds the desired match, panics if doesn't find the match
-        let mut bet_list: Vec<(String, String, f64, f64, PayedOut)> = Vec::new(); // Creates a new empty list where the required values will get added to 
+    // Input either the bettor's account or "all" to view all bets for that match
+    pub fn view_bets(&self, match_id: String, name: String) -> Vec<BetView> {
+        let current_match: Match = self.find_match(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id));  // Finds the desired match, panics if doesn't find the match
+        let mut bet_list: Vec<BetView> = Vec::new(); // Creates a new empty list where the required values will get added to 
         for i in 0..current_match.bets.len() { // Loops through all the bets for the match
-            let i: usize = i.try_into().unwrap();
             let username: String = (current_match.bets[i].bettor).to_string();
             if name == "all" || name == username { // If all selected then it will selected all the bets, if not it will selected the bets with the correct name
-                let team: String = (current_match.bets[i].decision).to_string(); // Seperates the infomation from the Bet struct into variables
+                let alliance: AllianceColour = current_match.bets[i].decision; // Seperates the infomation from the Bet struct into variables
                 let bet: f64 = current_match.bets[i].bet_amount;
                 let winnings: f64 = current_match.bets[i].potential_winnings;
                 let payed: PayedOut = current_match.bets[i].payed_out;
+                let in_play: bool = current_match.bets[i].in_play;
 
-                let individual_bet: (String, String, f64, f64, PayedOut) = (username, team, bet, winnings, payed); // Creates a tuple containing the information
+                let individual_bet: BetView = (i as u64, username, alliance, bet, winnings, payed, in_play); // Creates a tuple containing the information
                 bet_list.push(individual_bet); } // Adds the tuple to the list of bets that are to be displayed
             }
 
@@ -321,50 +596,162 @@ impl MatchList { // Implementation of MatchList
     }
 
 
-    // View function that allows the user to view what the potential winnings would be if they placed a bet on a certain match, on a certain team, with a certain amount
-    pub fn view_potential_winnings(&self, match_id: String, team: String, bet_amount: String) -> f64 {
+    // View function that allows the user to view what the potential winnings would be if they placed a bet on a certain match, on a certain alliance, with a certain amount
+    pub fn view_potential_winnings(&self, match_id: String, alliance: AllianceColour, bet_amount: String) -> f64 {
         let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
 
-        if team != current_match.team_1 && team != current_match.team_2 { // Checks valid team input 
-            panic!("That is not a valid team")
+        let bet_amount_f64: f64 = bet_amount.parse().unwrap();
+
+        current_match.pricing_model().winnings(&current_match.pools(), alliance.index(), bet_amount_f64) // Displays the potential winnings
+
+    }
+
+
+}
+
+
+impl Match { // Helper functions for a single match
+
+    // Returns the pools of both alliances in the order used by the pricing models
+    fn pools(&self) -> Vec<f64> {
+        vec![self.red_total_bets, self.blue_total_bets]
+    }
+
+    // Updates the pools of both alliances from the order used by the pricing models
+    fn set_pools(&mut self, pools: &[f64]) {
+        self.red_total_bets = pools[AllianceColour::Red.index()];
+        self.blue_total_bets = pools[AllianceColour::Blue.index()];
+    }
+
+    // Returns the pricing model of the match
+    fn pricing_model(&self) -> Box<dyn PricingModel> {
+        self.pricing.model(self.house_margin, self.virtual_liquidity)
+    }
+
+    // Returns the current unrounded decimal odds of both alliances
+    fn current_odds(&self) -> Vec<f64> {
+        if self.pricing == PricingKind::Exchange {
+            self.best_back_odds() // Gets the odds from the order book
+        } else {
+            self.pricing_model().odds(&self.pools())
         }
+    }
 
-        let bet_amount_f64: f64 = bet_amount.parse().unwrap();
+    // Returns the fair value of a bet at the current pools
+    // Finds the stake that would buy the bet's potential winnings now, the reverse of the pricing, then takes off the house margin that stake would include
+    fn cash_out_value(&self, bet_index: usize) -> f64 {
+        self.sale_stake(bet_index) / (1.0 + self.house_margin)
+    }
 
-        let mut potential_winnings: f64 = 0.0;
-        if team == current_match.team_1 { // If they have picked team 1
-            potential_winnings = find_winnings(current_match.team_1_total_bets, current_match.team_2_total_bets, bet_amount_f64);
-        } else if team == current_match.team_2 { // If they have picked team 2
-            potential_winnings = find_winnings(current_match.team_2_total_bets, current_match.team_1_total_bets, bet_amount_f64);
+    // Returns the stake the bet's potential winnings are sold back for, which is what is taken out of the pools when it is cashed out
+    // It is priced from the current pools rather than what the bet was placed at, so bets placed since can't make a round trip pay
+    fn sale_stake(&self, bet_index: usize) -> f64 {
+        let model: Box<dyn PricingModel> = self.pricing_model();
+        require!(!model.settles_at_finish(), "Bets on this match can't be cashed out");
+        let bet: &Bet = &self.bets[bet_index];
+        model.sale_stake(&self.pools(), bet.decision.index(), bet.bet_amount, bet.potential_winnings)
+    }
+
+    // Takes a bet back out of the pools and promised_winnings, input the sale stake if it was sold back rather than cancelled
+    fn remove_bet(&mut self, bet_index: usize, sale_stake: Option<f64>) {
+        let model: Box<dyn PricingModel> = self.pricing_model();
+        let (decision, bet_amount, potential_winnings): (AllianceColour, f64, f64) = (self.bets[bet_index].decision, self.bets[bet_index].bet_amount, self.bets[bet_index].potential_winnings);
+
+        let mut pools: Vec<f64> = self.pools();
+        match sale_stake {
+            Some(sale_stake) => model.sell_bet(&mut pools, decision.index(), bet_amount, sale_stake, potential_winnings),
+            None => model.remove_bet(&mut pools, decision.index(), bet_amount, potential_winnings),
         }
+        self.set_pools(&pools);
+        self.promised_winnings = self.promised_winnings_after(model.as_ref(), decision, -potential_winnings); // Takes the winnings off the side they were promised to
+    }
 
-        potential_winnings // Displays the potential winnings
+    // Returns what the promised_winnings of the match would be after a bet
+    fn promised_winnings_after(&self, model: &dyn PricingModel, decision: AllianceColour, potential_winnings: f64) -> f64 {
+        if model.settles_at_finish() { // Pari-mutuel and exchange winnings don't come from the house so nothing is promised
+            return self.promised_winnings
+        }
+        match decision {
+            AllianceColour::Red => self.promised_winnings + potential_winnings, // If they have picked the red alliance
+            AllianceColour::Blue => self.promised_winnings - potential_winnings, // If they have picked the blue alliance
+        }
+    }
 
+    // Returns how much the house would lose on each alliance winning, from the bets that are yet to be payed
+    // Negative values mean the house would make money if that alliance wins
+    fn liabilities(&self) -> Vec<f64> {
+        if self.pricing_model().settles_at_finish() { // The house carries no risk
+            return vec![0.0; 2]
+        }
+        let unsettled_bets = self.bets.iter().filter(|bet| bet.payed_out == PayedOut::YetToBePayed);
+        let total_stakes: f64 = unsettled_bets.clone().map(|bet| bet.bet_amount).sum(); // The house keeps every stake whichever alliance wins
+        let mut liabilities: Vec<f64> = vec![-total_stakes; 2];
+        for bet in unsettled_bets {
+            liabilities[bet.decision.index()] += bet.potential_winnings; // Then pays out the winners
+        }
+        liabilities
     }
 
+    // Returns the colour of the alliance a team is playing on, or None if the team isn't in this match
+    fn alliance_of(&self, team_number: &str) -> Option<AllianceColour> {
+        [&self.red_alliance, &self.blue_alliance].into_iter()
+            .find(|alliance| alliance.teams.iter().any(|team| team == team_number))
+            .map(|alliance| alliance.colour)
+    }
 
+    // Creates the view of the match that is returned by the view functions
+    fn view(&self, match_id: String) -> MatchView {
+        let odds: Vec<f64> = self.current_odds(); // Gets the odds for the game
+        MatchView{
+            match_id,
+            red_teams: self.red_alliance.teams.clone(),
+            red_odds: round_odds(odds[AllianceColour::Red.index()]),
+            blue_teams: self.blue_alliance.teams.clone(),
+            blue_odds: round_odds(odds[AllianceColour::Blue.index()]),
+            event: self.event.clone(),
+            division: self.division.clone(),
+            round: self.round,
+            match_number: self.match_number,
+            metadata: self.metadata.clone(),
+            house_margin: self.house_margin,
+            virtual_liquidity: self.virtual_liquidity,
+            pricing: self.pricing.clone(),
+            winner: self.winner,
+            red_score: self.red_score,
+            blue_score: self.blue_score,
+            match_state: self.match_state,
+        }
+    }
 }
 
 
-// Function that can only be called by the code. Gets the starting odds from the total bets and adds to 5% take
-fn find_starting_odds(team_1_total_bets: f64, team_2_total_bets: f64) -> (f64, f64) {
-    let total: f64 = team_1_total_bets + team_2_total_bets;
-    let divider: f64 = total / 1.05; // Gives the divider that makes implied probability add to 1.05
-    let implied_prob_1: f64 = team_1_total_bets / divider; // Finds the implied probabilty
-    let implied_prob_2: f64 = team_2_total_bets / divider;
-    let team_1_odds: f64 = (100.0 / implied_prob_1).round() / 100.0; // Gives the odds 
-    let team_2_odds: f64 = (100.0 / implied_prob_2).round() / 100.0;
+// Function that can only be called by the code. Forms the match_id from the event, division, round, match number and the date
+fn match_id(event: &Option<String>, division: &String, round: u32, match_number: u32, date: &String) -> String {
+    match event {
+        Some(sku) => format!("{}-{}-{}-{}-{}", sku, division, round, match_number, date),
+        None => format!("{}-{}-{}-{}", division, round, match_number, date),
+    }
+}
 
-    (team_1_odds, team_2_odds)
+
+// Function that can only be called by the code. Rounds decimal odds to two places
+fn round_odds(odds: f64) -> f64 {
+    (odds * 100.0).round() / 100.0
 }
 
 
-// Function that can only be called by the code. Finds the potentail winnings for a bet
-// The team that is being betted on goes first in the function call, and the other team is second
-// Intergrates over odds with bet amount
-fn find_winnings(betted_team_bets: f64, other_team: f64, bet_amount: f64) -> f64 {
-    let ln_target: f64 = (betted_team_bets + bet_amount) / betted_team_bets;
-    (1.0 / 1.05) * (bet_amount + other_team * ln_target.ln())
+// Function that can only be called by the code. Transfers an amount of USDC from the contract to an account
+fn transfer_usdc(receiver_id: AccountId, amount: f64, memo: &str, gas: near_sdk::Gas) -> Promise {
+    let args: Vec<u8> = json!({
+        "receiver_id": receiver_id,
+        "amount": (amount * ONE_USDC).to_string(),
+        "memo": memo,
+    }).to_string().into_bytes();
+    Promise::new(USDC_CONTRACT.parse().unwrap()).function_call("ft_transfer".to_string(), args, ONE_YOCTO, gas)
 }
 
 
+// Function that can only be called by the code. Returns the amount the contract has available to pay out bets
+fn available_funds() -> f64 {
+    (env::account_balance() / ONE_USDC as u128) as f64
+}
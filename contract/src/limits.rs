@@ -0,0 +1,138 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+const NANOSECONDS_PER_DAY: u64 = 86400000000000;
+
+// Struct that holds the limits on how much can be bet, limits that are null aren't enforced
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeLimits {
+    pub min_stake: f64, // Smallest amount that can be bet at once
+    pub max_stake: Option<f64>, // Largest amount that can be bet at once
+    pub max_match_stake_per_account: Option<f64>, // Most an account can bet on a single match in total
+    pub max_daily_stake_per_account: Option<f64>, // Most an account can bet across all matches in a UTC day
+    pub max_match_liability: Option<f64>, // Most the house can lose on a single match
+}
+
+impl StakeLimits {
+    // Panics if the limits don't make sense
+    fn validate(&self) {
+        require!(self.min_stake >= 0.0, "The minimum stake can't be negative");
+        for limit in [self.max_stake, self.max_match_stake_per_account, self.max_daily_stake_per_account].into_iter().flatten() {
+            require!(limit >= self.min_stake, "A maximum stake can't be less than the minimum stake");
+        }
+        require!(self.max_match_liability.is_none_or(|limit| limit >= 0.0), "The maximum liability can't be negative");
+    }
+
+    // Returns why an amount can't be bet at once, or None if it can
+    pub fn bet_size_error(&self, bet_amount: f64) -> Option<String> {
+        if bet_amount < self.min_stake {
+            return Some(format!("The minimum bet is {}", self.min_stake))
+        }
+        match self.max_stake {
+            Some(max_stake) if bet_amount > max_stake => Some(format!("The maximum bet is {}", max_stake)),
+            _ => None,
+        }
+    }
+}
+
+// Struct that holds how much an account has bet on a day
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DailyStake {
+    day: u64, // Days since the epoch
+    total: f64,
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the stake limits
+
+    // Private call function that allows the contract account to change the stake limits
+    #[private]
+    pub fn set_stake_limits(&mut self, stake_limits: StakeLimits) {
+        stake_limits.validate();
+        self.stake_limits = stake_limits;
+        log!("The stake limits have been updated")
+    }
+
+    // View function that allows the user to view the stake limits
+    pub fn view_stake_limits(&self) -> StakeLimits {
+        self.stake_limits.clone()
+    }
+
+    // View function that allows the user to view how much an account has bet today
+    pub fn view_daily_stake(&self, account_id: AccountId) -> f64 {
+        self.daily_stake(&account_id)
+    }
+}
+
+
+impl MatchList { // Helper functions for the stake limits
+
+    // Returns why a bet on a match would break the stake limits, or None if it wouldn't
+    // Limits on a single account are only checked if the bettor is known
+    pub(crate) fn stake_limit_error(&self, current_match: &Match, bettor: Option<&AccountId>, decision: AllianceColour, bet_amount: f64, potential_winnings: f64) -> Option<String> {
+        let limits: &StakeLimits = &self.stake_limits;
+        if let Some(error) = limits.bet_size_error(bet_amount) {
+            return Some(error)
+        }
+
+        if let Some(bettor) = bettor {
+            // Bets that were cancelled, cashed out or returned no longer count, and the escrow of exchange orders does
+            let match_stake: f64 = current_match.bets.iter()
+                .filter(|bet| &bet.bettor == bettor && bet.payed_out == PayedOut::YetToBePayed)
+                .map(|bet| bet.bet_amount)
+                .sum::<f64>() + current_match.exchange_stake(bettor);
+            if limits.max_match_stake_per_account.is_some_and(|limit| match_stake + bet_amount > limit) {
+                return Some("That would take you over the most you can bet on this match".to_string())
+            }
+            if let Some(error) = self.daily_limit_error(bettor, bet_amount) {
+                return Some(error)
+            }
+        }
+
+        if let Some(limit) = limits.max_match_liability {
+            if !current_match.pricing_model().settles_at_finish() {
+                // Every stake is kept and the winners are payed out, the same way as liabilities()
+                let mut liabilities: Vec<f64> = current_match.liabilities().iter().map(|liability| liability - bet_amount).collect();
+                liabilities[decision.index()] += potential_winnings;
+                if liabilities.iter().any(|liability| *liability > limit) {
+                    return Some("That would take the house over the most it can lose on this match".to_string())
+                }
+            }
+        }
+
+        None
+    }
+
+    // Returns why a bet would take an account over the most it can bet today, or None if it wouldn't
+    pub(crate) fn daily_limit_error(&self, bettor: &AccountId, bet_amount: f64) -> Option<String> {
+        if self.stake_limits.max_daily_stake_per_account.is_some_and(|limit| self.daily_stake(bettor) + bet_amount > limit) {
+            return Some("That would take you over the most you can bet today".to_string())
+        }
+        None
+    }
+
+    // Adds a bet to how much the account has bet today
+    pub(crate) fn record_daily_stake(&mut self, bettor: &AccountId, bet_amount: f64) {
+        let total: f64 = self.daily_stake(bettor) + bet_amount;
+        self.daily_stakes.insert(bettor, &DailyStake{day: today(), total});
+    }
+
+    // Returns how much the account has bet today
+    fn daily_stake(&self, bettor: &AccountId) -> f64 {
+        match self.daily_stakes.get(bettor) {
+            Some(daily_stake) if daily_stake.day == today() => daily_stake.total,
+            _ => 0.0, // Nothing has been bet yet today
+        }
+    }
+}
+
+
+// Function that can only be called by the code. Returns the number of days since the epoch
+fn today() -> u64 {
+    env::block_timestamp() / NANOSECONDS_PER_DAY
+}
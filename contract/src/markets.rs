@@ -0,0 +1,453 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+const MIN_OUTCOMES: usize = 2;
+const MAX_OUTCOMES: usize = 256; // Enough for every team at a large event
+
+// Enum that selects what a market is betting on
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum MarketKind {
+    Handicap { line: f64 }, // Red covers if its score plus the line beats the blue score, outcomes are "Red" and "Blue"
+    Total { line: f64 }, // Whether the two scores added together go over or under the line, outcomes are "Over" and "Under"
+    Proposition { question: String }, // Side event with outcomes named when the market is created, e.g. "Who wins the autonomous bonus?"
+    Futures { event: String }, // SKU of the event, long running market on who wins an event or division, stays open across many matches
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum MarketState {
+    Open,
+    Closed, // No more bets are taken but the market hasn't been settled yet
+    Settled,
+    Void, // The market was pushed or cancelled and every stake was returned
+}
+
+// Struct that holds a side market on a match, with its own outcomes, pools and pricing
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Market {
+    market_id: u64,
+    match_id: Option<String>, // Match the market is attached to, betting closes when it starts. Futures markets and propositions on an event aren't attached to a match
+    kind: MarketKind,
+    outcomes: Vec<String>, // Names of the outcomes, the index is used to bet on one
+    pools: Vec<f64>, // Pools of the pricing model for each outcome
+    pricing: PricingKind,
+    house_margin: f64,
+    virtual_liquidity: f64,
+    bets: Vec<MarketBet>, // List of bets made on the market, bet_id is the index
+    liabilities: Vec<f64>, // Winnings promised to the bets on each outcome
+    eliminated: Vec<bool>, // Outcomes that can no longer win, they are left out of the pricing and their bets are lost
+    winner: Option<usize>, // Index of the winning outcome
+    market_state: MarketState,
+}
+
+// Struct that holds the details of a single bet on a market
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketBet {
+    bettor: AccountId,
+    outcome: usize,
+    bet_amount: f64,
+    potential_winnings: f64,
+    payed_out: PayedOut,
+}
+
+// Struct returned by the market view functions
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketView {
+    market_id: u64,
+    match_id: Option<String>,
+    kind: MarketKind,
+    outcomes: Vec<String>,
+    odds: Vec<f64>, // Rounded decimal odds of each outcome, 0 for outcomes that have been eliminated
+    eliminated: Vec<bool>,
+    pricing: PricingKind,
+    winner: Option<usize>,
+    market_state: MarketState,
+}
+
+// Struct that a market bet is parsed into from the msg of ft_on_transfer
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketBetData {
+    pub market_id: u64,
+    pub outcome: usize,
+    pub min_potential_winnings: Option<f64>, // Smallest potential winnings the bettor will accept
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the side markets
+
+    // Private call function that allows the contract account to add a handicap or over/under market to a future match
+    // Input the odds of each outcome, otherwise both outcomes start even. The pricing model defaults to LogIntegral
+    #[private]
+    pub fn add_score_market(&mut self, match_id: String, kind: MarketKind, in_odds: Option<Vec<f64>>, pricing: Option<PricingKind>) {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let outcomes: Vec<String> = match kind {
+            MarketKind::Handicap { line } => {
+                require!(line.is_finite(), "The line must be a number");
+                vec!["Red".to_string(), "Blue".to_string()]
+            }
+            MarketKind::Total { line } => {
+                require!(line.is_finite() && line >= 0.0, "The line can't be negative");
+                vec!["Over".to_string(), "Under".to_string()]
+            }
+            MarketKind::Proposition { .. } | MarketKind::Futures { .. } => panic!("Only handicap and total markets are added with add_score_market"),
+        };
+
+        let market_id: u64 = self.create_market(Some(match_id.clone()), kind, outcomes, in_odds, pricing, self.config.overridden(Some(current_match.house_margin), Some(current_match.virtual_liquidity)));
+        log!("Market {} has been added to {}", market_id, match_id)
+    }
+
+    // Private call function that allows the contract account to add a proposition market with custom outcomes to a future match
+    // Input an event SKU instead of a match_id for a proposition on the whole event, e.g. "Which team scores the most points?"
+    // It is settled by a reporter with settle_proposition, separately from the result of the match
+    #[private]
+    pub fn add_proposition_market(&mut self, match_id: Option<String>, event: Option<String>, question: String, outcomes: Vec<String>, in_odds: Option<Vec<f64>>, pricing: Option<PricingKind>) {
+        require!(!question.is_empty(), "The proposition needs a question");
+
+        match (match_id, event) {
+            (Some(match_id), None) => {
+                let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+                let market_id: u64 = self.create_market(Some(match_id.clone()), MarketKind::Proposition { question }, outcomes, in_odds, pricing, self.config.overridden(Some(current_match.house_margin), Some(current_match.virtual_liquidity)));
+                log!("Market {} has been added to {}", market_id, match_id)
+            }
+            (None, Some(event)) => {
+                let market_id: u64 = self.create_market(None, MarketKind::Proposition { question }, outcomes, in_odds, pricing, self.config);
+                self.add_event_market(&event, market_id); // Panics if the event doesn't exist
+                log!("Market {} has been added to {}", market_id, event)
+            }
+            _ => panic!("Input either a match_id or an event"),
+        }
+    }
+
+    // Call function that allows a reporter to stop taking bets on a proposition on an event, a proposition on a match closes when the match starts
+    pub fn close_proposition(&mut self, market_id: u64) {
+        self.assert_reporter();
+        let mut market: Market = self.markets.get(&market_id).unwrap_or_else(|| panic!("No market exists with market_id: {}", market_id));
+        require!(market.kind.settled_by_reporter() && market.match_id.is_none(), "That isn't a proposition on an event");
+        market.close();
+        self.markets.insert(&market_id, &market);
+        log!("Betting has closed for market {}", market_id)
+    }
+
+    // Call function that allows a reporter to settle a proposition market once betting on it has closed
+    // Input the index of the winning outcome, or null to void the market and return every stake
+    pub fn settle_proposition(&mut self, market_id: u64, winning_outcome: Option<usize>) {
+        self.assert_reporter();
+        let mut market: Market = self.markets.get(&market_id).unwrap_or_else(|| panic!("No market exists with market_id: {}", market_id));
+
+        require!(market.kind.settled_by_reporter(), "That market is settled from the match result");
+        require!(!market.is_settled(), "That market has already been settled");
+        require!(!market.betting_open(self), "Betting is still open for that market");
+        require!(winning_outcome.is_none_or(|outcome| outcome < market.outcomes.len()), "That market has no such outcome");
+
+        self.settle_market(&mut market, winning_outcome);
+        self.markets.insert(&market_id, &market);
+        log!("Market {} has been settled", market_id)
+    }
+
+    // View function that allows the user to view a single market
+    pub fn view_market(&self, market_id: u64) -> MarketView {
+        self.markets.get(&market_id).unwrap_or_else(|| panic!("No market exists with market_id: {}", market_id)).view()
+    }
+
+    // View function that allows the user to view every market on a match
+    pub fn view_match_markets(&self, match_id: String) -> Vec<MarketView> {
+        self.markets.values().filter(|market| market.match_id.as_ref() == Some(&match_id)).map(|market| market.view()).collect()
+    }
+
+    // View function that allows the user to view the bets on a market, input either the bettor's account or "all"
+    pub fn view_market_bets(&self, market_id: u64, name: String) -> Vec<MarketBet> {
+        let market: Market = self.markets.get(&market_id).unwrap_or_else(|| panic!("No market exists with market_id: {}", market_id));
+        market.bets.into_iter().filter(|bet| name == "all" || bet.bettor.as_str() == name).collect()
+    }
+}
+
+
+impl MatchList { // Helper functions for the side markets
+
+    // Creates an open market, on a match if match_id is given, and returns its market_id
+    pub(crate) fn create_market(&mut self, match_id: Option<String>, kind: MarketKind, outcomes: Vec<String>, in_odds: Option<Vec<f64>>, pricing: Option<PricingKind>, market_config: Config) -> u64 {
+        require!((MIN_OUTCOMES..=MAX_OUTCOMES).contains(&outcomes.len()), format!("A market must have between {} and {} outcomes", MIN_OUTCOMES, MAX_OUTCOMES));
+        require!(outcomes.iter().enumerate().all(|(i, outcome)| !outcome.is_empty() && !outcomes[..i].contains(outcome)), "The outcomes must have different names");
+        let pricing: PricingKind = pricing.unwrap_or_default();
+        pricing.validate(outcomes.len());
+        let Config{house_margin, virtual_liquidity, ..} = market_config;
+        let model: Box<dyn PricingModel> = pricing.model(house_margin, virtual_liquidity);
+        require!(!model.settles_at_finish(), "Markets need a pricing model with fixed winnings");
+
+        // Changes the initial decimal odds to probabilities that add to 1
+        let probabilities: Vec<f64> = match in_odds {
+            Some(in_odds) => {
+                require!(in_odds.len() == outcomes.len() && in_odds.iter().all(|odds| *odds > 1.0), "Odds greater than 1 must be given for every outcome");
+                let divider: f64 = in_odds.iter().map(|odds| 1.0 / odds).sum();
+                in_odds.iter().map(|odds| 1.0 / odds / divider).collect()
+            }
+            None => vec![1.0 / outcomes.len() as f64; outcomes.len()],
+        };
+        let pools: Vec<f64> = model.seed_pools(&probabilities, virtual_liquidity);
+
+        let market_id: u64 = self.next_market_id;
+        self.next_market_id += 1;
+        let liabilities: Vec<f64> = vec![0.0; outcomes.len()];
+        let eliminated: Vec<bool> = vec![false; outcomes.len()];
+        let market: Market = Market{market_id, match_id: match_id.clone(), kind, outcomes, pools, pricing, house_margin, virtual_liquidity, bets: Vec::new(), liabilities, eliminated, winner: None, market_state: MarketState::Open};
+        self.markets.insert(&market_id, &market);
+
+        if let Some(match_id) = match_id { // Indexes the market by its match so it can be settled with it
+            let mut market_ids: Vec<u64> = self.match_markets.get(&match_id).unwrap_or_default();
+            market_ids.push(market_id);
+            self.match_markets.insert(&match_id, &market_ids);
+        }
+        market_id
+    }
+
+    // Places a bet on an open market, while its match is still in the future if it is attached to one
+    // Returns false if the bet wasn't placed because the price got worse than the bettor accepted
+    pub(crate) fn place_market_bet(&mut self, bettor: AccountId, bet_amount: f64, bet: MarketBetData) -> bool {
+        let mut market: Market = self.markets.get(&bet.market_id).unwrap_or_else(|| panic!("No market exists with market_id: {}", bet.market_id));
+        require!(market.betting_open(self), "Betting has closed for that market");
+        require!(bet.outcome < market.outcomes.len(), "That market has no such outcome");
+        require!(!market.eliminated[bet.outcome], "That outcome has been eliminated");
+        if let Some(error) = self.stake_limits.bet_size_error(bet_amount).or_else(|| self.daily_limit_error(&bettor, bet_amount)) {
+            panic!("{}", error)
+        }
+
+        let (active, mut pools, model): (Vec<usize>, Vec<f64>, Box<dyn PricingModel>) = market.active_pricing();
+        let position: usize = active.iter().position(|outcome| *outcome == bet.outcome).unwrap(); // Where the outcome is among the ones still in the market
+        let potential_winnings: f64 = model.winnings(&pools, position, bet_amount);
+        if bet.min_potential_winnings.is_some_and(|min| potential_winnings < min) {
+            log!("The potential winnings would have been {} which is worse than you accepted so the bet has been returned", potential_winnings);
+            return false
+        }
+
+        self.bet_counter -= market.reserved(); // Takes off the reserved winnings as they will change
+        model.apply_bet(&mut pools, position, bet_amount, potential_winnings);
+        for (outcome, pool) in active.into_iter().zip(pools) {
+            market.pools[outcome] = pool;
+        }
+        market.liabilities[bet.outcome] += potential_winnings;
+        self.bet_counter += market.reserved(); // Adds this back on with changed amount
+        if self.bet_counter >= available_funds() {
+            panic!("Sorry you can't make a bet as we wouldn't definetly be able to pay out")
+        }
+
+        self.record_daily_stake(&bettor, bet_amount);
+        market.bets.push(MarketBet{bettor, outcome: bet.outcome, bet_amount, potential_winnings, payed_out: PayedOut::YetToBePayed});
+        log!("You have made a bet on {} in market {}, with ${}, and potential winnings {}", market.outcomes[bet.outcome], bet.market_id, bet_amount, potential_winnings);
+        self.markets.insert(&bet.market_id, &market);
+        true
+    }
+
+    // Settles the markets on a match from the final scores, or voids them if the scores are None because the match was cancelled
+    // Pays out up to limit bets, only those of the bettor if one is given, and returns the number payed. The rest wait for a later call
+    // Propositions are left for a reporter to settle unless the match was cancelled
+    pub(crate) fn settle_match_markets(&mut self, match_id: &String, scores: Option<(u32, u32)>, limit: usize, bettor: Option<&AccountId>) -> usize {
+        let mut waiting: Vec<u64> = Vec::new(); // Markets that still have bets to pay out
+        let mut processed: usize = 0;
+        for market_id in self.match_markets.remove(match_id).unwrap_or_default() {
+            let mut market: Market = self.markets.get(&market_id).unwrap();
+            if market.is_settled() || (scores.is_some() && market.kind.settled_by_reporter()) {
+                continue
+            }
+            let winner: Option<usize> = scores.and_then(|(red_score, blue_score)| market.kind.winner(red_score, blue_score));
+            processed += self.pay_out_market_bets(&mut market, winner, limit - processed, bettor);
+            self.markets.insert(&market_id, &market);
+            if !market.is_settled() {
+                waiting.push(market_id);
+            }
+        }
+
+        if !waiting.is_empty() {
+            self.match_markets.insert(match_id, &waiting);
+        }
+        processed
+    }
+
+    // Returns the number of bets on the markets of a match that are yet to be payed out
+    pub(crate) fn unsettled_market_bets(&self, match_id: &String) -> usize {
+        self.match_markets.get(match_id).unwrap_or_default().iter()
+            .map(|market_id| self.markets.get(market_id).unwrap().bets.iter().filter(|bet| bet.payed_out == PayedOut::YetToBePayed).count())
+            .sum()
+    }
+
+    // Pays out every bet on the winning outcome of a market, or returns every stake if there is no winner
+    pub(crate) fn settle_market(&mut self, market: &mut Market, winner: Option<usize>) {
+        self.pay_out_market_bets(market, winner, usize::MAX, None);
+    }
+
+    // Pays out up to limit bets on a market, only those of the bettor if one is given, and settles the market once every bet has been
+    // Returns the number of bets payed out
+    fn pay_out_market_bets(&mut self, market: &mut Market, winner: Option<usize>, limit: usize, bettor: Option<&AccountId>) -> usize {
+        let mut processed: usize = 0;
+        for bet in market.bets.iter_mut().filter(|bet| bet.payed_out == PayedOut::YetToBePayed && bettor.is_none_or(|bettor| &bet.bettor == bettor)) {
+            if processed == limit {
+                break
+            }
+            match winner {
+                Some(winner) if winner == bet.outcome => {
+                    transfer_usdc(bet.bettor.clone(), bet.potential_winnings, "Winnings", near_sdk::Gas(30000000000000));
+                    bet.payed_out = PayedOut::Payed;
+                }
+                Some(_) => bet.payed_out = PayedOut::NotPayed,
+                None => {
+                    transfer_usdc(bet.bettor.clone(), bet.bet_amount, "Return funds", near_sdk::Gas(30000000000000));
+                    bet.payed_out = PayedOut::ReturnPay;
+                }
+            }
+            processed += 1;
+        }
+
+        if !market.is_settled() && market.bets.iter().all(|bet| bet.payed_out != PayedOut::YetToBePayed) {
+            self.bet_counter -= market.reserved(); // Releases the reserved winnings
+            market.winner = winner;
+            market.market_state = if winner.is_some() { MarketState::Settled } else { MarketState::Void };
+        }
+        processed
+    }
+}
+
+
+impl MarketKind {
+    // Returns true if the market is settled by a reporter rather than from the final scores
+    fn settled_by_reporter(&self) -> bool {
+        matches!(self, MarketKind::Proposition { .. })
+    }
+
+    // Returns the index of the winning outcome from the final scores, or None if the result lands exactly on the line
+    fn winner(&self, red_score: u32, blue_score: u32) -> Option<usize> {
+        let margin: f64 = match self {
+            MarketKind::Handicap { line } => red_score as f64 + line - blue_score as f64,
+            MarketKind::Total { line } => (red_score + blue_score) as f64 - line,
+            MarketKind::Proposition { .. } | MarketKind::Futures { .. } => panic!("That market is settled by a reporter"),
+        };
+        if margin > 0.0 {
+            Some(0) // Red covers or the total goes over
+        } else if margin < 0.0 {
+            Some(1)
+        } else {
+            None // Push
+        }
+    }
+}
+
+
+impl Market { // Helper functions for a single market
+
+    // Returns the outcomes that haven't been eliminated, their pools and a pricing model over just those outcomes
+    fn active_pricing(&self) -> (Vec<usize>, Vec<f64>, Box<dyn PricingModel>) {
+        let active: Vec<usize> = (0..self.outcomes.len()).filter(|outcome| !self.eliminated[*outcome]).collect();
+        let pools: Vec<f64> = active.iter().map(|outcome| self.pools[*outcome]).collect();
+        let mut pricing: PricingKind = self.pricing.clone();
+        if let PricingKind::FixedOdds { odds, .. } = &mut pricing {
+            *odds = active.iter().map(|outcome| odds[*outcome]).collect();
+        }
+        (active, pools, pricing.model(self.house_margin, self.virtual_liquidity))
+    }
+
+    // Returns the current unrounded decimal odds of each outcome, 0 for outcomes that have been eliminated
+    pub(crate) fn current_odds(&self) -> Vec<f64> {
+        let (active, pools, model): (Vec<usize>, Vec<f64>, Box<dyn PricingModel>) = self.active_pricing();
+        let mut odds: Vec<f64> = vec![0.0; self.outcomes.len()];
+        for (outcome, active_odds) in active.into_iter().zip(model.odds(&pools)) {
+            odds[outcome] = active_odds;
+        }
+        odds
+    }
+
+    // Returns true if bets are being taken, a market on a match stops taking them when the match starts
+    fn betting_open(&self, match_list: &MatchList) -> bool {
+        self.market_state == MarketState::Open && self.match_id.as_ref().is_none_or(|match_id| match_list.future_matches.get(match_id).is_some())
+    }
+
+    // Returns true if the market is taking bets, ignoring whether its match has started
+    pub(crate) fn is_open(&self) -> bool {
+        self.market_state == MarketState::Open
+    }
+
+    // Returns true if the market has been settled or voided
+    pub(crate) fn is_settled(&self) -> bool {
+        matches!(self.market_state, MarketState::Settled | MarketState::Void)
+    }
+
+    // Returns the amount held in the bet_counter for the market, enough to pay out whichever outcome has the most promised to it
+    pub(crate) fn reserved(&self) -> f64 {
+        self.liabilities.iter().cloned().fold(0.0, f64::max)
+    }
+
+    // Takes an outcome out of the market, the bets on it are lost and the house no longer owes their winnings
+    pub(crate) fn eliminate(&mut self, outcome: usize) {
+        require!(outcome < self.outcomes.len(), "That market has no such outcome");
+        require!(!self.eliminated[outcome], format!("{} has already been eliminated", self.outcomes[outcome]));
+        self.eliminated[outcome] = true;
+        require!(self.eliminated.iter().any(|eliminated| !eliminated), "Every outcome can't be eliminated");
+
+        for bet in self.bets.iter_mut().filter(|bet| bet.outcome == outcome && bet.payed_out == PayedOut::YetToBePayed) {
+            bet.payed_out = PayedOut::NotPayed;
+        }
+        self.liabilities[outcome] = 0.0;
+    }
+
+    // Stops the market taking bets before it is settled
+    pub(crate) fn close(&mut self) {
+        require!(self.market_state == MarketState::Open, "That market isn't open");
+        self.market_state = MarketState::Closed;
+    }
+
+    // Returns true if the market is a futures market
+    pub(crate) fn is_futures(&self) -> bool {
+        matches!(self.kind, MarketKind::Futures { .. })
+    }
+
+    // Returns true if the outcome exists and hasn't been eliminated
+    pub(crate) fn in_running(&self, outcome: usize) -> bool {
+        outcome < self.outcomes.len() && !self.eliminated[outcome]
+    }
+
+    // Creates the view of the market that is returned by the view functions
+    pub(crate) fn view(&self) -> MarketView {
+        MarketView{
+            market_id: self.market_id,
+            match_id: self.match_id.clone(),
+            kind: self.kind.clone(),
+            outcomes: self.outcomes.clone(),
+            odds: self.current_odds().into_iter().map(round_odds).collect(),
+            eliminated: self.eliminated.clone(),
+            pricing: self.pricing.clone(),
+            winner: self.winner,
+            market_state: self.market_state,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_on_the_line_is_a_push() {
+        assert_eq!(MarketKind::Handicap{line: 10.0}.winner(90, 100), None);
+        assert_eq!(MarketKind::Handicap{line: 0.0}.winner(100, 100), None);
+        assert_eq!(MarketKind::Total{line: 150.0}.winner(70, 80), None);
+        assert_eq!(MarketKind::Total{line: 150.0}.winner(71, 80), Some(0)); // Over
+        assert_eq!(MarketKind::Total{line: 150.0}.winner(69, 80), Some(1)); // Under
+    }
+
+    #[test]
+    fn negative_handicap_takes_points_off_red() {
+        let handicap: MarketKind = MarketKind::Handicap{line: -10.5};
+        assert_eq!(handicap.winner(111, 100), Some(0)); // Red wins by more than the line
+        assert_eq!(handicap.winner(110, 100), Some(1)); // Red wins but blue covers
+        assert_eq!(handicap.winner(95, 100), Some(1));
+        assert_eq!(MarketKind::Handicap{line: -10.0}.winner(110, 100), None);
+    }
+}
+
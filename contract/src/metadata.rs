@@ -0,0 +1,101 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::U64;
+use near_sdk::{near_bindgen, log};
+
+use crate::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RoundType {
+    Qualification,
+    Elimination,
+    Final,
+}
+
+// Struct that holds the optional details of a match shown to bettors
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchMetadata {
+    pub round_type: Option<RoundType>,
+    pub field: Option<String>, // Field the match is played on e.g. "Field 2"
+    pub stream_url: Option<String>, // Where the match can be watched
+    pub description: Option<String>,
+    pub start_time: Option<U64>, // Scheduled start as a block timestamp in nanoseconds
+}
+
+// Struct that holds the filters of search_matches, filters that are null aren't applied
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchFilter {
+    event: Option<String>, // SKU of the event
+    team_number: Option<String>,
+    round_type: Option<RoundType>,
+    match_state: Option<MatchState>,
+    start_from: Option<U64>, // Earliest start time, matches without a start time are left out when this or start_to is set
+    start_to: Option<U64>, // Latest start time
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of match metadata
+
+    // Private call function that allows the contract account to change the metadata of a match before it starts
+    #[private]
+    pub fn update_match_metadata(&mut self, match_id: String, metadata: MatchMetadata) {
+        let mut current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        metadata.validate();
+        current_match.metadata = metadata;
+        self.future_matches.insert(&match_id, &current_match); // Updates the match
+        log!("The metadata of {} has been updated", match_id)
+    }
+
+    // View function that allows the user to find matches, in any state, that pass every filter given
+    pub fn search_matches(&self, filter: MatchFilter) -> Vec<MatchView> {
+        let mut match_list: Vec<MatchView> = Vec::new(); // Creates a new empty list where the required values will get added to
+
+        for matches in [&self.future_matches, &self.in_progress_matches, &self.complete_matches, &self.error_matches] { // Loops through each of the match maps
+            for (key, current_match) in matches.iter() {
+                if filter.matches(&current_match) {
+                    match_list.push(current_match.view(key))
+                }
+            }
+        }
+
+        match_list // Returns the list
+    }
+}
+
+
+impl MatchMetadata {
+    // Panics if the metadata doesn't make sense
+    pub fn validate(&self) {
+        if let Some(error) = self.error() {
+            panic!("{}", error)
+        }
+    }
+
+    // Returns why the metadata doesn't make sense, or None if it does
+    pub fn error(&self) -> Option<String> {
+        if self.stream_url.as_ref().is_some_and(|url| !url.starts_with("https://") && !url.starts_with("http://")) {
+            return Some("The stream URL must be a web address".to_string())
+        }
+        None
+    }
+}
+
+
+impl MatchFilter {
+    // Returns true if a match passes every filter
+    fn matches(&self, current_match: &Match) -> bool {
+        let start_time: Option<u64> = current_match.metadata.start_time.map(|start_time| start_time.0);
+        let in_range: bool = (self.start_from.is_none() && self.start_to.is_none())
+            || start_time.is_some_and(|start_time| self.start_from.is_none_or(|from| start_time >= from.0) && self.start_to.is_none_or(|to| start_time <= to.0));
+
+        self.event.as_ref().is_none_or(|event| current_match.event.as_ref() == Some(event))
+            && self.team_number.as_ref().is_none_or(|team_number| current_match.alliance_of(team_number).is_some())
+            && self.round_type.is_none_or(|round_type| current_match.metadata.round_type == Some(round_type))
+            && self.match_state.is_none_or(|match_state| current_match.match_state == match_state)
+            && in_range
+    }
+}
@@ -0,0 +1,126 @@
+use near_sdk::serde::Serialize;
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+const MAX_FRACTION_DENOMINATOR: u64 = 100; // Largest denominator used when writing odds as a fraction
+
+// Struct that holds the odds of one outcome in every format
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OutcomeOdds {
+    outcome: AllianceColour,
+    decimal: f64, // Unrounded decimal odds, 0 if the outcome has no price yet
+    decimal_rounded: f64, // Decimal odds rounded to two places, as shown in the match views
+    fractional: Option<String>, // Profit to stake as a fraction e.g. "5/4"
+    american: Option<i64>, // Positive is the profit on a 100 stake, negative is the stake needed for a 100 profit
+    implied_probability: Option<f64>, // Inverse of the decimal odds, includes the house margin
+}
+
+// Struct returned by view_match_odds
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchOdds {
+    match_id: String,
+    outcomes: Vec<OutcomeOdds>, // Red first then blue
+    overround: Option<f64>, // How much the implied probabilities add up to more than 1, missing if an outcome has no price
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the odds formats view
+
+    // View function that allows the user to view the current odds of a match in decimal, fractional and American formats along with the implied probabilities
+    pub fn view_match_odds(&self, match_id: String) -> MatchOdds {
+        let current_match: Match = self.find_match(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let odds: Vec<f64> = current_match.current_odds();
+
+        let outcomes: Vec<OutcomeOdds> = [AllianceColour::Red, AllianceColour::Blue].into_iter()
+            .map(|outcome| outcome_odds(outcome, odds[outcome.index()]))
+            .collect();
+        let implied_probabilities: Option<Vec<f64>> = outcomes.iter().map(|outcome| outcome.implied_probability).collect();
+        let overround: Option<f64> = implied_probabilities.map(|probabilities| probabilities.iter().sum::<f64>() - 1.0);
+
+        MatchOdds{match_id, outcomes, overround}
+    }
+}
+
+
+// Function that can only be called by the code. Converts decimal odds into every format
+fn outcome_odds(outcome: AllianceColour, decimal: f64) -> OutcomeOdds {
+    let priced: bool = decimal > 1.0; // Odds of 1 or less can't be shown as a profit
+    OutcomeOdds{
+        outcome,
+        decimal,
+        decimal_rounded: round_odds(decimal),
+        fractional: priced.then(|| to_fractional(decimal)),
+        american: priced.then(|| to_american(decimal)),
+        implied_probability: (decimal > 0.0).then(|| 1.0 / decimal),
+    }
+}
+
+
+// Function that can only be called by the code. Writes decimal odds as a fraction of profit to stake
+// Uses the continued fraction of the profit to find the closest fraction with a small denominator
+fn to_fractional(decimal: f64) -> String {
+    let profit: f64 = decimal - 1.0;
+    let (mut numerator, mut denominator): (u64, u64) = (profit.round() as u64, 1); // Closest whole number, used if there is no closer fraction
+    let (mut previous_numerator, mut previous_denominator): (u64, u64) = (1, 0); // The last two convergents of the continued fraction
+    let (mut current_numerator, mut current_denominator): (u64, u64) = (profit.floor() as u64, 1);
+    let mut remainder: f64 = profit.fract();
+
+    while remainder > 0.000001 {
+        let inverse: f64 = 1.0 / remainder;
+        let term: u64 = inverse.floor() as u64;
+        let next_numerator: u64 = term * current_numerator + previous_numerator;
+        let next_denominator: u64 = term * current_denominator + previous_denominator;
+        if next_denominator > MAX_FRACTION_DENOMINATOR {
+            break
+        }
+        (previous_numerator, previous_denominator) = (current_numerator, current_denominator);
+        (current_numerator, current_denominator) = (next_numerator, next_denominator);
+        (numerator, denominator) = (current_numerator, current_denominator);
+        remainder = inverse - inverse.floor();
+    }
+
+    format!("{}/{}", numerator, denominator)
+}
+
+
+// Function that can only be called by the code. Writes decimal odds as American odds
+fn to_american(decimal: f64) -> i64 {
+    if decimal >= 2.0 {
+        ((decimal - 1.0) * 100.0).round() as i64 // Underdog, profit on a 100 stake
+    } else {
+        (-100.0 / (decimal - 1.0)).round() as i64 // Favourite, stake needed for a 100 profit
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractional_odds_are_exact_for_simple_prices() {
+        assert_eq!(to_fractional(3.0), "2/1");
+        assert_eq!(to_fractional(2.25), "5/4");
+        assert_eq!(to_fractional(1.5), "1/2");
+        assert_eq!(to_fractional(1.0 + 10.0 / 11.0), "10/11");
+    }
+
+    #[test]
+    fn fractional_odds_use_the_last_convergent_within_the_denominator_limit() {
+        // The convergents of pi are 3/1, 22/7 then 333/106, which has too large a denominator
+        assert_eq!(to_fractional(1.0 + std::f64::consts::PI), "22/7");
+        // The convergents of 0.6180... are 0/1, 1/1, 1/2, 2/3, 3/5 ... 55/89 then 89/144
+        assert_eq!(to_fractional(1.0 + (5f64.sqrt() - 1.0) / 2.0), "55/89");
+    }
+
+    #[test]
+    fn american_odds_switch_sign_at_evens() {
+        assert_eq!(to_american(2.0), 100);
+        assert_eq!(to_american(3.5), 250);
+        assert_eq!(to_american(1.5), -200);
+    }
+}
@@ -0,0 +1,154 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::U64;
+use near_sdk::{env, AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+// Struct that holds a result proposed by a reporter, it can be disputed until the challenge window has passed
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposedResult {
+    proposer: AccountId,
+    red_score: u32,
+    blue_score: u32,
+    evidence_url: String, // Where the result can be checked e.g. the RobotEvents match page
+    proposed_at: U64, // Block timestamp the challenge window starts from
+    dispute: Option<Dispute>,
+}
+
+// Struct that holds a dispute of a proposed result, backed by a bond that is returned if the arbiter agrees with it
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Dispute {
+    disputer: AccountId,
+    red_score: u32, // The result the disputer says is correct
+    blue_score: u32,
+    evidence_url: String,
+    bond: f64,
+}
+
+// Struct that a dispute is parsed into from the msg of ft_on_transfer, the amount transferred is the bond
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DisputeData {
+    pub dispute_match_id: String,
+    pub red_score: u32,
+    pub blue_score: u32,
+    pub evidence_url: String,
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the result oracle
+
+    // Private call function that allows the contract account to give an account the arbiter role, arbiters resolve disputed results
+    #[private]
+    pub fn add_arbiter(&mut self, account_id: AccountId) {
+        self.arbiters.insert(&account_id);
+        log!("{} is now an arbiter", account_id)
+    }
+
+    // Private call function that allows the contract account to take the arbiter role away from an account
+    #[private]
+    pub fn remove_arbiter(&mut self, account_id: AccountId) {
+        require!(self.arbiters.remove(&account_id), "That account is not an arbiter");
+        log!("{} is no longer an arbiter", account_id)
+    }
+
+    // Call function that allows a reporter to propose the result of a match in progress, with a link to evidence of the result
+    // The result can be disputed until the challenge window in the config has passed
+    pub fn propose_result(&mut self, match_id: String, red_score: u32, blue_score: u32, evidence_url: String) {
+        self.assert_reporter();
+        if let Some(error) = self.result_error(&match_id) {
+            panic!("{}", error)
+        }
+        require!(evidence_url.starts_with("https://") || evidence_url.starts_with("http://"), "The evidence URL must be a web address");
+
+        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap();
+        current_match.close_in_play(); // Stops in-play betting and returns the pending bets, the result is public from now on
+        current_match.proposed_result = Some(ProposedResult{proposer: env::predecessor_account_id(), red_score, blue_score, evidence_url, proposed_at: U64(env::block_timestamp()), dispute: None});
+        self.in_progress_matches.insert(&match_id, &current_match); // Updates the match
+        log!("A result of {} to {} has been proposed for {}, it can be disputed for {} nanoseconds", red_score, blue_score, match_id, self.config.challenge_window.0)
+    }
+
+    // Call function that anyone can call to make a proposed result final once the challenge window has passed without a dispute
+    // The match becomes complete and its bets can be claimed or payed out with process_payouts
+    pub fn finalize_result(&mut self, match_id: String) {
+        let current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let proposed_result: &ProposedResult = current_match.proposed_result.as_ref().unwrap_or_else(|| panic!("No result has been proposed for that match"));
+
+        require!(proposed_result.dispute.is_none(), "The result has been disputed and must be resolved by an arbiter");
+        require!(env::block_timestamp() >= proposed_result.proposed_at.0 + self.config.challenge_window.0, "The challenge window hasn't passed yet");
+
+        self.record_result(&match_id, proposed_result.red_score, proposed_result.blue_score);
+        log!("The result of {} is final", match_id)
+    }
+
+    // Call function that allows an arbiter to settle a disputed result with the correct scores
+    // The bond is returned to the disputer if the scores are the ones they disputed with, otherwise the house keeps it
+    pub fn resolve_dispute(&mut self, match_id: String, red_score: u32, blue_score: u32) {
+        self.assert_arbiter();
+        let current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let dispute: &Dispute = current_match.proposed_result.as_ref().and_then(|proposed_result| proposed_result.dispute.as_ref()).unwrap_or_else(|| panic!("The result of that match hasn't been disputed"));
+
+        if dispute.red_score == red_score && dispute.blue_score == blue_score {
+            transfer_usdc(dispute.disputer.clone(), dispute.bond, "Dispute bond", near_sdk::Gas(30000000000000));
+            log!("The dispute was upheld and the bond has been returned");
+        } else {
+            log!("The dispute was rejected and the bond has been kept");
+        }
+
+        self.record_result(&match_id, red_score, blue_score);
+        log!("The result of {} is final", match_id)
+    }
+
+    // View function that allows the user to view the result proposed for a match in progress and any dispute of it
+    pub fn view_proposed_result(&self, match_id: String) -> Option<ProposedResult> {
+        let current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        current_match.proposed_result
+    }
+
+    // View function that allows the user to view all accounts with the arbiter role
+    pub fn view_arbiters(&self) -> Vec<AccountId> {
+        self.arbiters.to_vec()
+    }
+}
+
+
+impl MatchList { // Helper functions for the result oracle
+
+    // Disputes the proposed result of a match with a bond of bet_amount, which must be at least the dispute bond in the config
+    pub(crate) fn dispute_result(&mut self, disputer: AccountId, bond: f64, dispute: DisputeData) {
+        let match_id: String = dispute.dispute_match_id;
+        let mut current_match: Match = self.in_progress_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let challenge_window: u64 = self.config.challenge_window.0;
+        let proposed_result: &mut ProposedResult = current_match.proposed_result.as_mut().unwrap_or_else(|| panic!("No result has been proposed for that match"));
+
+        require!(proposed_result.dispute.is_none(), "That result has already been disputed");
+        require!(env::block_timestamp() < proposed_result.proposed_at.0 + challenge_window, "The challenge window has passed");
+        require!(bond >= self.config.dispute_bond, format!("The dispute bond is {}", self.config.dispute_bond));
+        require!(dispute.red_score != proposed_result.red_score || dispute.blue_score != proposed_result.blue_score, "That is the result that was proposed");
+
+        proposed_result.dispute = Some(Dispute{disputer, red_score: dispute.red_score, blue_score: dispute.blue_score, evidence_url: dispute.evidence_url, bond});
+        self.in_progress_matches.insert(&match_id, &current_match); // Updates the match
+        log!("The result of {} has been disputed and will be resolved by an arbiter", match_id)
+    }
+
+    // Panics if the caller is neither an arbiter nor the contract account
+    fn assert_arbiter(&self) {
+        let caller: AccountId = env::predecessor_account_id();
+        require!(caller == env::current_account_id() || self.arbiters.contains(&caller), "Only an arbiter can call this function");
+    }
+}
+
+
+impl Match { // Helper functions for proposed results
+
+    // Returns the bond of any dispute of the proposed result, done when the match is cancelled
+    pub(crate) fn return_dispute_bond(&mut self) {
+        if let Some(dispute) = self.proposed_result.as_mut().and_then(|proposed_result| proposed_result.dispute.take()) {
+            transfer_usdc(dispute.disputer, dispute.bond, "Dispute bond", near_sdk::Gas(30000000000000));
+        }
+    }
+}
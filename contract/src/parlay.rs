@@ -0,0 +1,217 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+const MIN_PARLAY_LEGS: usize = 2;
+const MAX_PARLAY_LEGS: usize = 10;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum LegResult {
+    Pending,
+    Won,
+    Lost,
+    Void, // The match was cancelled so the leg drops out of the parlay
+}
+
+// Struct that holds one pick of a parlay
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ParlayLeg {
+    match_id: String,
+    decision: AllianceColour,
+    odds: f64, // Decimal odds of the pick when the parlay was placed
+    result: LegResult,
+}
+
+// Struct that holds an accumulator bet made up of picks on several matches, it only wins if every pick wins
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Parlay {
+    parlay_id: u64,
+    bettor: AccountId,
+    legs: Vec<ParlayLeg>,
+    bet_amount: f64,
+    potential_winnings: f64, // Bet amount multiplied by the odds of every leg, reserved in the bet_counter until the parlay is settled
+    payed_out: PayedOut,
+}
+
+// Struct that a parlay leg is parsed into from the msg of ft_on_transfer
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LegData {
+    match_id: String,
+    decision: AllianceColour,
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of parlays
+
+    // View function that allows the user to view a single parlay
+    pub fn view_parlay(&self, parlay_id: u64) -> Parlay {
+        self.parlays.get(&parlay_id).unwrap_or_else(|| panic!("No parlay exists with parlay_id: {}", parlay_id))
+    }
+
+    // View function that allows the user to view the parlays of an account, or "all" to view every parlay
+    pub fn view_parlays(&self, name: String) -> Vec<Parlay> {
+        self.parlays.values().filter(|parlay| name == "all" || parlay.bettor.as_str() == name).collect()
+    }
+}
+
+
+impl MatchList { // Helper functions for parlays
+
+    // Places a parlay priced at the product of the current odds of each leg, the pools of the matches aren't changed
+    pub(crate) fn place_parlay(&mut self, bettor: AccountId, bet_amount: f64, legs: Vec<LegData>) {
+        require!((MIN_PARLAY_LEGS..=MAX_PARLAY_LEGS).contains(&legs.len()), format!("A parlay must have between {} and {} legs", MIN_PARLAY_LEGS, MAX_PARLAY_LEGS));
+        if let Some(error) = self.stake_limits.bet_size_error(bet_amount).or_else(|| self.daily_limit_error(&bettor, bet_amount)) {
+            panic!("{}", error)
+        }
+
+        require!(self.stake_limits.max_match_liability.is_none(), "Parlays aren't taken while the house's liability on each match is limited");
+
+        let mut parlay_legs: Vec<ParlayLeg> = Vec::new();
+        for leg in legs {
+            require!(parlay_legs.iter().all(|other: &ParlayLeg| other.match_id != leg.match_id), "A parlay can only have one leg on each match");
+            let current_match: Match = self.future_matches.get(&leg.match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", leg.match_id));
+            require!(!current_match.pricing_model().settles_at_finish(), "Parlays can only include matches with fixed winnings");
+            require!(!current_match.has_liability_limits(), "Parlays can't include a match with liability limits"); // Parlay winnings aren't counted in the liabilities of each match
+            let odds: f64 = current_match.current_odds()[leg.decision.index()];
+            parlay_legs.push(ParlayLeg{match_id: leg.match_id, decision: leg.decision, odds, result: LegResult::Pending});
+        }
+
+        let potential_winnings: f64 = bet_amount * parlay_legs.iter().map(|leg| leg.odds).product::<f64>();
+        self.bet_counter += potential_winnings; // Reserves the winnings across every leg
+        if self.bet_counter >= available_funds() {
+            panic!("Sorry you can't make a bet as we wouldn't definetly be able to pay out")
+        }
+
+        let parlay_id: u64 = self.next_parlay_id;
+        self.next_parlay_id += 1;
+        for leg in parlay_legs.iter() { // Indexes the parlay by each of its matches so it can be settled as they finish
+            let mut parlay_ids: Vec<u64> = self.match_parlays.get(&leg.match_id).unwrap_or_default();
+            parlay_ids.push(parlay_id);
+            self.match_parlays.insert(&leg.match_id, &parlay_ids);
+        }
+        self.record_daily_stake(&bettor, bet_amount);
+        self.parlays.insert(&parlay_id, &Parlay{parlay_id, bettor, legs: parlay_legs, bet_amount, potential_winnings, payed_out: PayedOut::YetToBePayed});
+        log!("You have made parlay {} with ${}, and potential winnings {}", parlay_id, bet_amount, potential_winnings)
+    }
+
+    // Records the result of a match on up to limit parlays with a leg on it, only those of the bettor if one is given
+    // A winner of None means the match was cancelled or tied. Returns the number of parlays processed, the rest wait for a later call
+    // A parlay is lost as soon as one leg loses, and payed once every leg has won or been voided
+    pub(crate) fn settle_parlay_legs(&mut self, match_id: &String, winner: Option<AllianceColour>, limit: usize, bettor: Option<&AccountId>) -> usize {
+        let mut waiting: Vec<u64> = Vec::new(); // Parlays that are left for a later call
+        let mut processed: usize = 0;
+        for parlay_id in self.match_parlays.remove(match_id).unwrap_or_default() {
+            let mut parlay: Parlay = self.parlays.get(&parlay_id).unwrap();
+            if processed == limit || bettor.is_some_and(|bettor| &parlay.bettor != bettor) {
+                waiting.push(parlay_id);
+                continue
+            }
+            processed += 1;
+            if parlay.payed_out != PayedOut::YetToBePayed {
+                continue // Already lost on an earlier leg
+            }
+
+            let leg: &mut ParlayLeg = parlay.legs.iter_mut().find(|leg| &leg.match_id == match_id).unwrap();
+            leg.result = match winner {
+                Some(winner) if winner == leg.decision => LegResult::Won,
+                Some(_) => LegResult::Lost,
+                None => LegResult::Void,
+            };
+
+            if parlay.legs.iter().any(|leg| leg.result == LegResult::Lost) {
+                parlay.payed_out = PayedOut::NotPayed;
+                self.bet_counter -= parlay.potential_winnings; // Releases the reserved winnings
+            } else if parlay.legs.iter().all(|leg| leg.result != LegResult::Pending) {
+                let odds: f64 = parlay.legs.iter().filter(|leg| leg.result == LegResult::Won).map(|leg| leg.odds).product(); // Voided legs drop out
+                let winnings: f64 = parlay.bet_amount * odds;
+                let all_void: bool = parlay.legs.iter().all(|leg| leg.result == LegResult::Void);
+                transfer_usdc(parlay.bettor.clone(), winnings, if all_void { "Return funds" } else { "Winnings" }, near_sdk::Gas(30000000000000));
+                parlay.payed_out = if all_void { PayedOut::ReturnPay } else { PayedOut::Payed };
+                self.bet_counter -= parlay.potential_winnings; // Releases the reserved winnings
+                parlay.potential_winnings = winnings; // Records what was actually payed
+            }
+
+            self.parlays.insert(&parlay_id, &parlay);
+        }
+
+        if !waiting.is_empty() {
+            self.match_parlays.insert(match_id, &waiting);
+        }
+        processed
+    }
+
+    // Returns the number of parlays with a leg on the match that are yet to be processed
+    pub(crate) fn unsettled_parlays(&self, match_id: &String) -> usize {
+        self.match_parlays.get(match_id).map_or(0, |parlay_ids| parlay_ids.len())
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    // Returns a contract holding one parlay of 10 with a leg at the odds on each match, with its winnings reserved
+    fn placed_parlay(legs: &[(&str, AllianceColour, f64)]) -> MatchList {
+        testing_env!(VMContextBuilder::new().build());
+        let mut match_list: MatchList = MatchList::default();
+        let legs: Vec<ParlayLeg> = legs.iter().map(|(match_id, decision, odds)| ParlayLeg{match_id: match_id.to_string(), decision: *decision, odds: *odds, result: LegResult::Pending}).collect();
+        let potential_winnings: f64 = 10.0 * legs.iter().map(|leg| leg.odds).product::<f64>();
+        for leg in &legs {
+            match_list.match_parlays.insert(&leg.match_id, &vec![0]);
+        }
+        match_list.parlays.insert(&0, &Parlay{parlay_id: 0, bettor: "alice.near".parse().unwrap(), legs, bet_amount: 10.0, potential_winnings, payed_out: PayedOut::YetToBePayed});
+        match_list.bet_counter = potential_winnings;
+        match_list
+    }
+
+    #[test]
+    fn void_leg_drops_out_of_the_odds() {
+        let mut match_list: MatchList = placed_parlay(&[("a", AllianceColour::Red, 2.0), ("b", AllianceColour::Blue, 3.0), ("c", AllianceColour::Red, 1.5)]);
+        match_list.settle_parlay_legs(&"a".to_string(), Some(AllianceColour::Red), 10, None);
+        match_list.settle_parlay_legs(&"b".to_string(), None, 10, None);
+        match_list.settle_parlay_legs(&"c".to_string(), Some(AllianceColour::Red), 10, None);
+
+        let parlay: Parlay = match_list.parlays.get(&0).unwrap();
+        assert!(parlay.payed_out == PayedOut::Payed);
+        assert_eq!(parlay.potential_winnings, 10.0 * 2.0 * 1.5);
+        assert_eq!(match_list.bet_counter, 0.0);
+    }
+
+    #[test]
+    fn all_void_parlay_returns_the_stake() {
+        let mut match_list: MatchList = placed_parlay(&[("a", AllianceColour::Red, 2.0), ("b", AllianceColour::Blue, 3.0)]);
+        match_list.settle_parlay_legs(&"a".to_string(), None, 10, None);
+        match_list.settle_parlay_legs(&"b".to_string(), None, 10, None);
+
+        let parlay: Parlay = match_list.parlays.get(&0).unwrap();
+        assert!(parlay.payed_out == PayedOut::ReturnPay);
+        assert_eq!(parlay.potential_winnings, 10.0);
+        assert_eq!(match_list.bet_counter, 0.0);
+    }
+
+    #[test]
+    fn lost_leg_releases_the_reservation_once() {
+        let mut match_list: MatchList = placed_parlay(&[("a", AllianceColour::Red, 2.0), ("b", AllianceColour::Blue, 3.0)]);
+        match_list.bet_counter += 100.0; // Reserved for other bets
+        match_list.settle_parlay_legs(&"a".to_string(), Some(AllianceColour::Blue), 10, None);
+        assert_eq!(match_list.bet_counter, 100.0);
+        assert!(match_list.parlays.get(&0).unwrap().payed_out == PayedOut::NotPayed);
+
+        match_list.settle_parlay_legs(&"b".to_string(), Some(AllianceColour::Blue), 10, None); // The later leg would have won
+        assert_eq!(match_list.bet_counter, 100.0);
+        assert!(match_list.parlays.get(&0).unwrap().payed_out == PayedOut::NotPayed);
+        assert_eq!(match_list.unsettled_parlays(&"b".to_string()), 0);
+    }
+}
+
@@ -0,0 +1,416 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::require;
+
+const SEARCH_STEPS: u32 = 100; // Number of halvings used when searching for a stake, enough to reach the precision of an f64
+
+// Enum that selects which pricing model a match uses, chosen when the match is created
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PricingKind {
+    #[default]
+    LogIntegral, // Continuous model where the odds are integrated over the bet amount
+    Lmsr, // Logarithmic market scoring rule
+    PariMutuel, // Stakes form a pool and winners split the losing side's pool after the house margin is raked, payed out when the match finishes
+    Exchange, // Users bet against each other through an order book, the house margin is the commission taken from net winnings
+    FixedOdds { // Decimal odds for each outcome that are set by the operator and don't move with bets
+        odds: Vec<f64>,
+        #[serde(default)]
+        odds_version: u32, // Goes up by one every time the operator changes the odds
+        #[serde(default)]
+        liability_limits: Option<Vec<f64>>, // Most the house can lose on each outcome before bets on it stop being accepted
+    },
+}
+
+impl PricingKind {
+    // Returns the pricing model for a match with the given house margin and virtual liquidity
+    pub fn model(&self, house_margin: f64, virtual_liquidity: f64) -> Box<dyn PricingModel> {
+        match self {
+            PricingKind::LogIntegral => Box::new(LogIntegral{house_margin}),
+            PricingKind::Lmsr => Box::new(Lmsr{liquidity: virtual_liquidity, house_margin}),
+            PricingKind::PariMutuel => Box::new(PariMutuel{rake: house_margin}),
+            PricingKind::Exchange => Box::new(Exchange{}),
+            PricingKind::FixedOdds { odds, .. } => Box::new(FixedOdds{odds: odds.clone()}),
+        }
+    }
+
+    // Panics if the pricing kind can't be used for a market with this many outcomes
+    pub fn validate(&self, outcomes: usize) {
+        if let Some(error) = self.error(outcomes) {
+            panic!("{}", error)
+        }
+    }
+
+    // Returns why the pricing kind can't be used for a market with this many outcomes, or None if it can
+    pub fn error(&self, outcomes: usize) -> Option<String> {
+        if let PricingKind::FixedOdds { odds, liability_limits, .. } = self {
+            if odds.len() != outcomes {
+                return Some("Fixed odds must be given for every outcome".to_string())
+            }
+            if !odds.iter().all(|odds| *odds > 1.0) {
+                return Some("Fixed odds must be greater than 1".to_string())
+            }
+            if let Some(liability_limits) = liability_limits {
+                if liability_limits.len() != outcomes {
+                    return Some("A liability limit must be given for every outcome".to_string())
+                }
+                if !liability_limits.iter().all(|limit| *limit >= 0.0) {
+                    return Some("Liability limits can't be negative".to_string())
+                }
+            }
+        }
+        None
+    }
+
+    // Returns the version of the odds if the operator sets them
+    pub fn odds_version(&self) -> Option<u32> {
+        match self {
+            PricingKind::FixedOdds { odds_version, .. } => Some(*odds_version),
+            _ => None,
+        }
+    }
+}
+
+
+// Trait that every pricing model implements
+// The pools hold the state of the market for each outcome, what they represent depends on the model
+pub trait PricingModel {
+    // Returns the initial pools for a market, in line with the opening probability of each outcome
+    // The pools aren't rounded as a long shot on a small virtual liquidity would round to an empty pool and infinite odds
+    fn seed_pools(&self, probabilities: &[f64], virtual_liquidity: f64) -> Vec<f64> {
+        let pools: Vec<f64> = probabilities.iter().map(|prob| prob * virtual_liquidity).collect();
+        require!(pools.iter().all(|pool| *pool > 0.0), "Every outcome needs a chance of winning");
+        pools
+    }
+
+    // Returns how much a bet of bet_amount on the outcome would pay out if it wins, including the stake
+    fn winnings(&self, pools: &[f64], outcome: usize, bet_amount: f64) -> f64;
+
+    // Returns the current decimal odds of each outcome
+    fn odds(&self, pools: &[f64]) -> Vec<f64>;
+
+    // Returns the bet amount on the outcome that would give potential winnings of target_winnings, the inverse of winnings
+    // Searches numerically as winnings always go up with the bet amount
+    fn stake_for_winnings(&self, pools: &[f64], outcome: usize, target_winnings: f64) -> f64 {
+        let mut upper: f64 = target_winnings; // Winnings are at least the stake for every model, so this is usually enough
+        while self.winnings(pools, outcome, upper) < target_winnings {
+            upper *= 2.0;
+        }
+        let mut lower: f64 = 0.0;
+        for _ in 0..SEARCH_STEPS {
+            let middle: f64 = (lower + upper) / 2.0;
+            if self.winnings(pools, outcome, middle) < target_winnings {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        upper
+    }
+
+    // Returns the stake that would buy target_winnings on the outcome now if it hadn't been bought already, given pools that include it
+    // Selling winnings back at this stake and taking it out of the pools is the reverse of buying them, whatever else was bet in between
+    fn sale_stake(&self, pools: &[f64], outcome: usize, bet_amount: f64, target_winnings: f64) -> f64 {
+        let mut pools: Vec<f64> = pools.to_vec();
+        self.remove_bet(&mut pools, outcome, bet_amount, target_winnings); // These models undo a bet from its winnings, so its own stake can come out first
+        self.stake_for_winnings(&pools, outcome, target_winnings)
+    }
+
+    // Updates the pools after a bet has been placed
+    fn apply_bet(&self, pools: &mut [f64], outcome: usize, bet_amount: f64, _winnings: f64) {
+        pools[outcome] += bet_amount;
+    }
+
+    // Updates the pools after a bet has been taken back out, the reverse of apply_bet
+    fn remove_bet(&self, pools: &mut [f64], outcome: usize, bet_amount: f64, _winnings: f64) {
+        pools[outcome] -= bet_amount;
+    }
+
+    // Updates the pools after a bet has been sold back for sale_stake, which comes out as the reverse of buying its winnings now
+    fn sell_bet(&self, pools: &mut [f64], outcome: usize, _bet_amount: f64, sale_stake: f64, winnings: f64) {
+        self.remove_bet(pools, outcome, sale_stake, winnings);
+    }
+
+    // Returns true if winnings are only known once the match finishes, so nothing is promised when the bet is placed
+    fn settles_at_finish(&self) -> bool {
+        false
+    }
+
+    // Returns how much a winning bet pays out once the match has finished, given the final pools
+    fn settle(&self, _pools: &[f64], _winning_outcome: usize, _bet_amount: f64, potential_winnings: f64) -> f64 {
+        potential_winnings
+    }
+}
+
+
+// The original pricing model, the pools hold the total bets on each outcome
+pub struct LogIntegral {
+    house_margin: f64,
+}
+
+impl PricingModel for LogIntegral {
+    fn winnings(&self, pools: &[f64], outcome: usize, bet_amount: f64) -> f64 {
+        let other_bets: f64 = pools.iter().sum::<f64>() - pools[outcome]; // Total bets on every other outcome
+        find_winnings(pools[outcome], other_bets, bet_amount, self.house_margin)
+    }
+
+    fn odds(&self, pools: &[f64]) -> Vec<f64> {
+        let total: f64 = pools.iter().sum();
+        let divider: f64 = total / (1.0 + self.house_margin); // Gives the divider that makes implied probability add to 1 plus the house margin
+        pools.iter().map(|pool| divider / pool).collect() // Odds are the inverse of the implied probabilty
+    }
+    // The pools hold stakes so the stake also decides the pools it was bought from, searches for the stake that is consistent with both
+    fn sale_stake(&self, pools: &[f64], outcome: usize, _bet_amount: f64, target_winnings: f64) -> f64 {
+        let winnings_before = |stake: f64| {
+            let mut pools_before: Vec<f64> = pools.to_vec();
+            pools_before[outcome] -= stake;
+            self.winnings(&pools_before, outcome, stake)
+        };
+        let (mut lower, mut upper): (f64, f64) = (0.0, pools[outcome]); // Winnings grow without limit as the stake nears the whole pool
+        for _ in 0..SEARCH_STEPS {
+            let middle: f64 = (lower + upper) / 2.0;
+            if winnings_before(middle) < target_winnings {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        lower
+    }
+}
+
+
+// Logarithmic market scoring rule, the pools hold the number of shares sold for each outcome, each share pays out 1 if it wins
+pub struct Lmsr {
+    liquidity: f64, // The b parameter, larger values make prices move less
+    house_margin: f64,
+}
+
+impl Lmsr {
+    // Returns the sum of exp(q / b) over every outcome, which the cost function and prices are built from
+    fn exp_sum(&self, pools: &[f64]) -> f64 {
+        pools.iter().map(|shares| (shares / self.liquidity).exp()).sum()
+    }
+
+    // Returns the number of shares of the outcome that bet_amount buys before the house margin is taken
+    fn shares_bought(&self, pools: &[f64], outcome: usize, bet_amount: f64) -> f64 {
+        let exp_sum: f64 = self.exp_sum(pools);
+        let exp_outcome: f64 = (pools[outcome] / self.liquidity).exp();
+        // Solves C(q + shares) - C(q) = bet_amount where C(q) = b * ln(exp_sum)
+        self.liquidity * (1.0 + exp_sum * ((bet_amount / self.liquidity).exp() - 1.0) / exp_outcome).ln()
+    }
+}
+
+impl PricingModel for Lmsr {
+    fn seed_pools(&self, probabilities: &[f64], _virtual_liquidity: f64) -> Vec<f64> {
+        probabilities.iter().map(|prob| self.liquidity * prob.ln()).collect() // Prices start equal to the probabilities
+    }
+
+    fn winnings(&self, pools: &[f64], outcome: usize, bet_amount: f64) -> f64 {
+        self.shares_bought(pools, outcome, bet_amount) / (1.0 + self.house_margin)
+    }
+
+    fn odds(&self, pools: &[f64]) -> Vec<f64> {
+        let exp_sum: f64 = self.exp_sum(pools);
+        pools.iter().map(|shares| exp_sum / ((shares / self.liquidity).exp() * (1.0 + self.house_margin))).collect() // Inverse of the price plus the house margin
+    }
+
+    fn apply_bet(&self, pools: &mut [f64], outcome: usize, _bet_amount: f64, winnings: f64) {
+        pools[outcome] += winnings * (1.0 + self.house_margin); // Adds the shares that were sold
+    }
+
+    fn remove_bet(&self, pools: &mut [f64], outcome: usize, _bet_amount: f64, winnings: f64) {
+        pools[outcome] -= winnings * (1.0 + self.house_margin); // Buys the shares back
+    }
+}
+
+
+// Pari-mutuel pool, the pools hold the real stakes on each outcome with no virtual liquidity so the house carries no risk
+// Winners get their stake back plus a share of the losing pools, in proportion to their stake, after the rake
+pub struct PariMutuel {
+    rake: f64, // Fraction of the losing pools kept by the house
+}
+
+impl PariMutuel {
+    // Returns what a bet pays out if its outcome wins with the given pools, which already include the bet
+    fn payout(&self, pools: &[f64], outcome: usize, bet_amount: f64) -> f64 {
+        let losing_bets: f64 = pools.iter().sum::<f64>() - pools[outcome];
+        bet_amount + bet_amount / pools[outcome] * losing_bets * (1.0 - self.rake)
+    }
+}
+
+impl PricingModel for PariMutuel {
+    fn seed_pools(&self, probabilities: &[f64], _virtual_liquidity: f64) -> Vec<f64> {
+        vec![0.0; probabilities.len()] // Only real stakes go into the pool
+    }
+
+    // Estimate assuming no one else bets before the match starts
+    fn winnings(&self, pools: &[f64], outcome: usize, bet_amount: f64) -> f64 {
+        let mut pools_after: Vec<f64> = pools.to_vec();
+        pools_after[outcome] += bet_amount;
+        self.payout(&pools_after, outcome, bet_amount)
+    }
+
+    fn odds(&self, pools: &[f64]) -> Vec<f64> {
+        let total: f64 = pools.iter().sum();
+        pools.iter().map(|pool| {
+            if *pool == 0.0 {
+                0.0 // No price until something has been bet on the outcome
+            } else {
+                1.0 + (total - pool) * (1.0 - self.rake) / pool
+            }
+        }).collect()
+    }
+
+    fn settles_at_finish(&self) -> bool {
+        true
+    }
+
+    fn settle(&self, pools: &[f64], winning_outcome: usize, bet_amount: f64, _potential_winnings: f64) -> f64 {
+        self.payout(pools, winning_outcome, bet_amount)
+    }
+}
+
+
+// Exchange, bets are priced and matched by the order book of the match rather than by the pools
+pub struct Exchange {}
+
+impl PricingModel for Exchange {
+    fn seed_pools(&self, probabilities: &[f64], _virtual_liquidity: f64) -> Vec<f64> {
+        vec![0.0; probabilities.len()] // The house doesn't provide any liquidity
+    }
+
+    fn winnings(&self, _pools: &[f64], _outcome: usize, _bet_amount: f64) -> f64 {
+        panic!("Exchange matches are priced by the order book")
+    }
+
+    fn odds(&self, pools: &[f64]) -> Vec<f64> {
+        vec![0.0; pools.len()] // Prices come from the order book
+    }
+
+    fn settles_at_finish(&self) -> bool {
+        true // Users are matched against each other so the house promises nothing
+    }
+}
+
+
+// Fixed odds, the operator sets the odds of each outcome and bets don't move them
+pub struct FixedOdds {
+    odds: Vec<f64>,
+}
+
+impl PricingModel for FixedOdds {
+    fn seed_pools(&self, probabilities: &[f64], _virtual_liquidity: f64) -> Vec<f64> {
+        vec![0.0; probabilities.len()] // The pools only keep track of the real stakes on each outcome
+    }
+
+    fn winnings(&self, _pools: &[f64], outcome: usize, bet_amount: f64) -> f64 {
+        bet_amount * self.odds[outcome]
+    }
+
+    fn odds(&self, _pools: &[f64]) -> Vec<f64> {
+        self.odds.clone()
+    }
+
+    fn stake_for_winnings(&self, _pools: &[f64], outcome: usize, target_winnings: f64) -> f64 {
+        target_winnings / self.odds[outcome]
+    }
+
+    fn sell_bet(&self, pools: &mut [f64], outcome: usize, bet_amount: f64, _sale_stake: f64, winnings: f64) {
+        self.remove_bet(pools, outcome, bet_amount, winnings); // The pools are the real stakes, whatever the bet is sold back for
+    }
+}
+
+
+// Function that can only be called by the code. Finds the potentail winnings for a bet
+// The outcome that is being betted on goes first in the function call, and the other outcomes are second
+// Intergrates over odds with bet amount
+fn find_winnings(betted_bets: f64, other_bets: f64, bet_amount: f64, house_margin: f64) -> f64 {
+    let ln_target: f64 = (betted_bets + bet_amount) / betted_bets;
+    (1.0 / (1.0 + house_margin)) * (bet_amount + other_bets * ln_target.ln())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sells a bet back at the current pools and takes its sale stake out of them, returning what was payed
+    fn cash_out(model: &dyn PricingModel, pools: &mut [f64], bet_amount: f64, winnings: f64) -> f64 {
+        let stake: f64 = model.sale_stake(pools, 0, bet_amount, winnings);
+        model.sell_bet(pools, 0, bet_amount, stake, winnings);
+        stake
+    }
+
+    #[test]
+    fn log_integral_round_trip_returns_the_stake() {
+        let model: LogIntegral = LogIntegral{house_margin: 0.0};
+        let mut pools: Vec<f64> = vec![100.0, 100.0];
+        let winnings: f64 = model.winnings(&pools, 0, 100.0);
+        model.apply_bet(&mut pools, 0, 100.0, winnings);
+
+        assert!((cash_out(&model, &mut pools, 100.0, winnings) - 100.0).abs() < 0.000001);
+        assert!((pools[0] - 100.0).abs() < 0.000001);
+    }
+
+    #[test]
+    fn log_integral_cash_out_never_pays_more_than_was_staked() {
+        let model: LogIntegral = LogIntegral{house_margin: 0.0};
+        let mut pools: Vec<f64> = vec![100.0, 100.0];
+        let first: f64 = model.winnings(&pools, 0, 100.0);
+        model.apply_bet(&mut pools, 0, 100.0, first);
+        let second: f64 = model.winnings(&pools, 0, 100.0);
+        model.apply_bet(&mut pools, 0, 100.0, second);
+
+        // Cashing out the oldest bet first used to return more than the 200 staked
+        let payed: f64 = cash_out(&model, &mut pools, 100.0, first) + cash_out(&model, &mut pools, 100.0, second);
+        assert!(payed <= 200.000001);
+        assert!(pools[0] >= 100.0 - 0.000001);
+    }
+
+    #[test]
+    fn lmsr_sale_stake_is_the_cost_of_the_shares() {
+        let model: Lmsr = Lmsr{liquidity: 100.0, house_margin: 0.0};
+        let mut pools: Vec<f64> = model.seed_pools(&[0.5, 0.5], 0.0);
+        let winnings: f64 = model.winnings(&pools, 0, 50.0);
+        model.apply_bet(&mut pools, 0, 50.0, winnings);
+
+        assert!((model.sale_stake(&pools, 0, 50.0, winnings) - 50.0).abs() < 0.000001);
+    }
+
+    #[test]
+    fn fixed_odds_cash_out_takes_the_stake_out_of_the_pools() {
+        let model: FixedOdds = FixedOdds{odds: vec![2.0, 2.0]};
+        let mut pools: Vec<f64> = vec![0.0, 0.0];
+        model.apply_bet(&mut pools, 0, 100.0, 200.0);
+        model.apply_bet(&mut pools, 0, 50.0, 100.0);
+
+        let model: FixedOdds = FixedOdds{odds: vec![4.0, 1.25]}; // The operator lengthens the odds so the first bet sells for less than its stake
+        assert_eq!(cash_out(&model, &mut pools, 100.0, 200.0), 50.0);
+        assert_eq!(pools, vec![50.0, 0.0]); // Only the other bet's stake is left
+    }
+
+    #[test]
+    fn pari_mutuel_winners_and_rake_share_the_whole_pool() {
+        let model: PariMutuel = PariMutuel{rake: 0.1};
+        let mut pools: Vec<f64> = vec![0.0, 0.0, 0.0];
+        for (outcome, bet_amount) in [(0, 100.0), (0, 200.0), (1, 150.0), (2, 50.0)] {
+            model.apply_bet(&mut pools, outcome, bet_amount, 0.0);
+        }
+
+        let payed: f64 = model.settle(&pools, 0, 100.0, 0.0) + model.settle(&pools, 0, 200.0, 0.0);
+        let rake: f64 = 0.1 * (150.0 + 50.0); // Only the losing pools are raked
+        assert!((payed + rake - 500.0).abs() < 0.000001);
+        assert!((model.settle(&pools, 0, 100.0, 0.0) - (100.0 + 200.0 * 0.9 / 3.0)).abs() < 0.000001); // In proportion to the stake
+    }
+
+    #[test]
+    fn pari_mutuel_stakes_on_the_winner_are_not_raked() {
+        let model: PariMutuel = PariMutuel{rake: 0.1};
+        let pools: Vec<f64> = vec![300.0, 0.0];
+
+        assert_eq!(model.settle(&pools, 0, 100.0, 0.0), 100.0); // Nothing was lost so there is nothing to rake
+        assert_eq!(model.odds(&pools), vec![1.0, 0.0]);
+    }
+}
+
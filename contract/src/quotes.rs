@@ -0,0 +1,155 @@
+use near_sdk::serde::Serialize;
+use near_sdk::{AccountId, near_bindgen, require};
+
+use crate::*;
+
+const MAX_BET_DOUBLINGS: u32 = 64; // Stakes up to 2^64 are tried before a match is treated as having no maximum bet
+const SEARCH_STEPS: u32 = 100; // Number of halvings used when searching for the maximum bet
+
+// Struct returned by simulate_bet that shows the effect a bet would have on a match
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BetSimulation {
+    potential_winnings: f64, // How much the bet would pay out if it wins, including the stake
+    average_odds: f64, // Potential winnings divided by the bet amount
+    odds_before: Vec<f64>, // Decimal odds of each alliance, red first, before the bet
+    odds_after: Vec<f64>, // Decimal odds of each alliance after the bet
+    liability_change: Vec<f64>, // Change in how much the house would lose on each alliance winning
+    passes_solvency_check: bool, // False if the contract couldn't be sure of paying out the bet
+    passes_limit_check: bool, // False if the bet would take the match over one of its limits
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the quoting views
+
+    // View function that allows the user to see the effect a bet would have before placing it, so large bets can be warned about
+    // Input the bettor to also check the limits on a single account
+    pub fn simulate_bet(&self, match_id: String, outcome: AllianceColour, bet_amount: String, bettor: Option<AccountId>) -> BetSimulation {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let bet_amount: f64 = bet_amount.parse().unwrap();
+
+        let model: Box<dyn PricingModel> = current_match.pricing_model();
+        let mut pools: Vec<f64> = current_match.pools();
+        let odds_before: Vec<f64> = model.odds(&pools);
+        let potential_winnings: f64 = model.winnings(&pools, outcome.index(), bet_amount);
+        model.apply_bet(&mut pools, outcome.index(), bet_amount, potential_winnings);
+        let odds_after: Vec<f64> = model.odds(&pools);
+
+        // Works out the change in liability the same way as liabilities(), every stake is kept and the winners are payed out
+        let liability_change: Vec<f64> = if model.settles_at_finish() {
+            vec![0.0; 2] // The house carries no risk
+        } else {
+            let mut liability_change: Vec<f64> = vec![-bet_amount; 2];
+            liability_change[outcome.index()] += potential_winnings;
+            liability_change
+        };
+
+        BetSimulation{
+            potential_winnings,
+            average_odds: potential_winnings / bet_amount,
+            odds_before,
+            odds_after,
+            liability_change,
+            passes_solvency_check: self.passes_solvency_check(&current_match, model.as_ref(), outcome, potential_winnings),
+            passes_limit_check: current_match.within_liability_limit(outcome, bet_amount, potential_winnings) && self.stake_limit_error(&current_match, bettor.as_ref(), outcome, bet_amount, potential_winnings).is_none(),
+        }
+    }
+
+    // View function that allows the user to find how much they need to bet on an alliance to get potential winnings of target_payout
+    pub fn quote_stake_for_payout(&self, match_id: String, outcome: AllianceColour, target_payout: f64) -> f64 {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        require!(target_payout > 0.0, "The target payout must be more than 0");
+        current_match.pricing_model().stake_for_winnings(&current_match.pools(), outcome.index(), target_payout)
+    }
+
+    // View function that allows the user to find the largest bet on an alliance that the contract would currently accept
+    // Input the bettor to also check the limits on a single account, returns null if there is no maximum and 0 if no bet would be accepted
+    pub fn max_bet(&self, match_id: String, outcome: AllianceColour, bettor: Option<AccountId>) -> Option<f64> {
+        let current_match: Match = self.future_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+        let model: Box<dyn PricingModel> = current_match.pricing_model();
+
+        // Anything smaller than the minimum stake is refused, so the search starts from it
+        let min_stake: f64 = self.stake_limits.min_stake;
+        if !self.accepts_bet(&current_match, model.as_ref(), outcome, bettor.as_ref(), min_stake) {
+            return Some(0.0)
+        }
+
+        // Doubles the stake until a bet is refused to find a stake that is too large
+        let mut lower: f64 = min_stake;
+        let mut upper: f64 = min_stake.max(1.0);
+        let mut doublings: u32 = 0;
+        while self.accepts_bet(&current_match, model.as_ref(), outcome, bettor.as_ref(), upper) {
+            if doublings == MAX_BET_DOUBLINGS {
+                return None
+            }
+            lower = upper;
+            upper *= 2.0;
+            doublings += 1;
+        }
+
+        // Then halves the gap between the largest accepted stake and the smallest refused stake
+        for _ in 0..SEARCH_STEPS {
+            let middle: f64 = (lower + upper) / 2.0;
+            if self.accepts_bet(&current_match, model.as_ref(), outcome, bettor.as_ref(), middle) {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        Some(lower)
+    }
+}
+
+
+impl MatchList { // Helper functions for the quoting views
+
+    // Returns false if the contract couldn't be sure of paying out after a bet, works out the bet_counter the same way as ft_on_transfer
+    fn passes_solvency_check(&self, current_match: &Match, model: &dyn PricingModel, outcome: AllianceColour, potential_winnings: f64) -> bool {
+        let promised_winnings: f64 = current_match.promised_winnings_after(model, outcome, potential_winnings);
+        let bet_counter: f64 = self.bet_counter - current_match.promised_winnings.abs() + promised_winnings.abs();
+        bet_counter < available_funds()
+    }
+
+    // Returns true if a bet would currently be accepted by ft_on_transfer
+    fn accepts_bet(&self, current_match: &Match, model: &dyn PricingModel, outcome: AllianceColour, bettor: Option<&AccountId>, bet_amount: f64) -> bool {
+        let potential_winnings: f64 = model.winnings(&current_match.pools(), outcome.index(), bet_amount);
+        self.passes_solvency_check(current_match, model, outcome, potential_winnings)
+            && current_match.within_liability_limit(outcome, bet_amount, potential_winnings)
+            && self.stake_limit_error(current_match, bettor, outcome, bet_amount, potential_winnings).is_none()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    // Returns a contract with 1000 USDC of funds and one future match at even odds
+    fn match_list() -> (MatchList, String) {
+        testing_env!(VMContextBuilder::new().account_balance(1000 * ONE_USDC as u128).build());
+        let mut match_list: MatchList = MatchList::default();
+        match_list.create_match(vec!["1A".to_string()], vec!["2B".to_string()], "Science".to_string(), 1, 1, Some(2.0), Some(2.0), "2024-01-01".to_string(), None, None, None, None, None);
+        let match_id: String = match_id(&None, &"Science".to_string(), 1, 1, &"2024-01-01".to_string());
+        (match_list, match_id)
+    }
+
+    #[test]
+    fn max_bet_below_one_is_found() {
+        let (mut match_list, match_id) = match_list();
+        match_list.stake_limits = StakeLimits{min_stake: 0.6, max_stake: Some(0.8), ..Default::default()};
+
+        let max_bet: f64 = match_list.max_bet(match_id, AllianceColour::Red, None).unwrap();
+        assert!((max_bet - 0.8).abs() < 0.000001);
+    }
+
+    #[test]
+    fn max_bet_is_zero_when_the_minimum_is_refused() {
+        let (mut match_list, match_id) = match_list();
+        match_list.stake_limits = StakeLimits{min_stake: 5000.0, ..Default::default()}; // More than the contract could pay out
+
+        assert_eq!(match_list.max_bet(match_id, AllianceColour::Red, None), Some(0.0));
+    }
+}
@@ -0,0 +1,158 @@
+#![allow(clippy::too_many_arguments)] // near_bindgen only passes cfg attributes on to the MatchListExt function it generates for create_match
+
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::{I64, U64};
+use near_sdk::{env, near_bindgen, log, Gas};
+
+use crate::*;
+use crate::metadata::MatchMetadata;
+
+const GAS_PER_MATCH: Gas = Gas(20000000000000); // Gas kept back for creating each match, the rest of the schedule is skipped once less than this is left
+
+// Struct that holds the details of one match in a schedule, the same as the arguments of create_match
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchSpec {
+    pub(crate) red_teams: Vec<String>,
+    pub(crate) blue_teams: Vec<String>,
+    pub(crate) division: String,
+    pub(crate) round: u32,
+    pub(crate) match_number: u32,
+    pub(crate) in_odds_red: Option<f64>,
+    pub(crate) in_odds_blue: Option<f64>,
+    pub(crate) date: String,
+    pub(crate) house_margin: Option<f64>,
+    pub(crate) virtual_liquidity: Option<f64>,
+    pub(crate) pricing: Option<PricingKind>,
+    pub(crate) event: Option<String>,
+    pub(crate) metadata: Option<MatchMetadata>,
+}
+
+// Struct returned by create_matches for each match in the schedule, in the same order
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchCreation {
+    match_id: String,
+    created: bool,
+    error: Option<String>, // Why the match wasn't created
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of match schedules
+
+    // Private call function that allows the contract account to create a new match, need to input the alliances, where the match is played, odds and the date of the match
+    // If the odds are left out they are found from the ratings of the teams in the registry
+    // The house margin and virtual liquidity can be set for this match, otherwise the contract defaults are used
+    // The pricing model defaults to LogIntegral. Input the SKU of an event to add the match to it, the division must be one of the event's
+    // The metadata can be left out and added later with update_match_metadata
+    #[private]
+    pub fn create_match(&mut self, red_teams: Vec<String>, blue_teams: Vec<String>, division: String, round: u32, match_number: u32, in_odds_red: Option<f64>, in_odds_blue: Option<f64>, date: String, house_margin: Option<f64>, virtual_liquidity: Option<f64>, pricing: Option<PricingKind>, event: Option<String>, metadata: Option<MatchMetadata>) {
+        let spec: MatchSpec = MatchSpec{red_teams, blue_teams, division, round, match_number, in_odds_red, in_odds_blue, date, house_margin, virtual_liquidity, pricing, event, metadata};
+        if let Some(error) = self.create_match_error(&spec) {
+            panic!("{}", error)
+        }
+        let match_id: String = spec.match_id();
+        let MatchSpec{red_teams, blue_teams, division, round, match_number, in_odds_red, in_odds_blue, date: _, house_margin, virtual_liquidity, pricing, event, metadata} = spec;
+        let match_config: Config = self.config.overridden(house_margin, virtual_liquidity); // Applies the overrides for this match
+        let pricing: PricingKind = pricing.unwrap_or_default();
+        let metadata: MatchMetadata = metadata.unwrap_or_default();
+
+        // Creates inital bets pool inline with odds
+        let (actual_prob_red, actual_prob_blue): (f64, f64) = match (in_odds_red, in_odds_blue) {
+            (Some(in_odds_red), Some(in_odds_blue)) => {
+                let in_prob_red: f64 = 1.0 / in_odds_red; // Changes initial decimal odds to initial probability
+                let in_prob_blue: f64 = 1.0 / in_odds_blue;
+                let divider: f64 = in_prob_red + in_prob_blue; // Creates the divider by adding implied odds
+                (in_prob_red / divider, in_prob_blue / divider) // Divides initial probability to give actual probability
+            }
+            (None, None) => self.rating_probabilities(&red_teams, &blue_teams), // Uses the team registry instead
+            _ => panic!("Either input odds for both alliances or for neither"),
+        };
+        let model: Box<dyn PricingModel> = pricing.model(match_config.house_margin, match_config.virtual_liquidity);
+        let pools: Vec<f64> = model.seed_pools(&[actual_prob_red, actual_prob_blue], match_config.virtual_liquidity); // Sets the initial pools using the virtual liquidity
+        let red_total_bets: f64 = pools[AllianceColour::Red.index()];
+        let blue_total_bets: f64 = pools[AllianceColour::Blue.index()];
+
+        let bets: Vec<Bet> = Vec::new(); // Creates a new empty bets list that holds all the bets
+        let orders: Vec<Order> = Vec::new();
+        let matched_bets: Vec<MatchedBet> = Vec::new();
+        let odds_history: OddsHistory = OddsHistory::new(self.config.odds_history_size, self.config.odds_snapshot_interval.0);
+        let red_alliance: Alliance = Alliance{colour: AllianceColour::Red, teams: red_teams};
+        let blue_alliance: Alliance = Alliance{colour: AllianceColour::Blue, teams: blue_teams};
+        let in_play: Option<InPlay> = None;
+        let winner: Option<AllianceColour> = None;
+        let red_score: Option<u32> = None;
+        let blue_score: Option<u32> = None;
+        let proposed_result: Option<ProposedResult> = None;
+        let match_state: MatchState = MatchState::Future;
+        let promised_winnings: f64 = 0.0;
+        let house_margin: f64 = match_config.house_margin;
+        let virtual_liquidity: f64 = match_config.virtual_liquidity;
+        if let Some(sku) = &event {
+            self.add_event_match(sku, &division, match_id.clone());
+        }
+        let mut new_match: Match = Match{bets, red_alliance, blue_alliance, event, division, round, match_number, metadata, red_total_bets, blue_total_bets, promised_winnings, house_margin, virtual_liquidity, pricing, orders, matched_bets, odds_history, in_play, winner, red_score, blue_score, proposed_result, match_state}; // Creates a new_match using the Match struct
+        new_match.record_odds(); // Records the opening odds
+        self.future_matches.insert(&match_id, &new_match); // Adds this new_match to the future_matches map
+        log!("A new match has been added with ID {}", match_id)
+    }
+
+    // Private call function that allows the contract account to create a whole schedule of matches at once
+    // A match that can't be created is reported and skipped rather than stopping the rest. If the gas runs out the remaining matches are
+    // reported as not created so they can be submitted again in another call
+    #[private]
+    pub fn create_matches(&mut self, specs: Vec<MatchSpec>) -> Vec<MatchCreation> {
+        let mut creations: Vec<MatchCreation> = Vec::new();
+        let mut created: u32 = 0;
+
+        for spec in specs {
+            let match_id: String = spec.match_id();
+            let error: Option<String> = if env::prepaid_gas() - env::used_gas() < GAS_PER_MATCH {
+                Some("There wasn't enough gas left, submit this match again".to_string())
+            } else {
+                self.create_match_error(&spec)
+            };
+
+            if error.is_none() {
+                self.create_match(spec.red_teams, spec.blue_teams, spec.division, spec.round, spec.match_number, spec.in_odds_red, spec.in_odds_blue, spec.date, spec.house_margin, spec.virtual_liquidity, spec.pricing, spec.event, spec.metadata);
+                created += 1;
+            }
+            creations.push(MatchCreation{match_id, created: error.is_none(), error});
+        }
+
+        log!("{} of {} matches have been created", created, creations.len());
+        creations
+    }
+
+    // Private call function that moves the start time of every future match in an event by shift nanoseconds, done when the event runs late
+    // Only matches starting at or after from are moved, input null to move them all. Matches without a start time are left alone
+    #[private]
+    pub fn update_match_schedule(&mut self, sku: String, shift: I64, from: Option<U64>) {
+        let event: Event = self.view_event(sku.clone());
+        let mut moved: u32 = 0;
+
+        for match_id in event.match_ids() {
+            if let Some(mut current_match) = self.future_matches.get(match_id) {
+                if let Some(start_time) = current_match.metadata.start_time {
+                    if from.is_none_or(|from| start_time.0 >= from.0) {
+                        let new_start_time: u64 = start_time.0.checked_add_signed(shift.0).unwrap_or_else(|| panic!("The start time of {} can't be moved that far", match_id));
+                        current_match.metadata.start_time = Some(U64(new_start_time));
+                        self.future_matches.insert(match_id, &current_match); // Updates the match
+                        moved += 1;
+                    }
+                }
+            }
+        }
+
+        log!("The start times of {} matches in {} have been moved by {} nanoseconds", moved, sku, shift.0)
+    }
+}
+
+
+impl MatchSpec {
+    // Returns the match_id the match will be created with
+    pub(crate) fn match_id(&self) -> String {
+        match_id(&self.event, &self.division, self.round, self.match_number, &self.date)
+    }
+}
@@ -0,0 +1,272 @@
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, near_bindgen, require, log, Gas};
+
+use std::cmp::Ordering;
+
+use crate::*;
+
+const GAS_PER_RESULT: Gas = Gas(20000000000000); // Gas kept back for recording each result, the rest are skipped once less than this is left
+
+// Struct that holds the final scores of a match
+#[derive(Serialize, Deserialize, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchResult {
+    red_score: u32,
+    blue_score: u32,
+}
+
+// Struct returned by settle_matches for each result, in the same order
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MatchSettlement {
+    match_id: String,
+    settled: bool,
+    error: Option<String>, // Why the result wasn't recorded
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of bulk settlement
+
+    // Private call function that allows the contract account to record the results of many matches at once
+    // Like finish_match this is an emergency override of the result oracle, the results are final straight away with no challenge window
+    // or dispute. Results should normally go through propose_result and finalize_result
+    // The bets aren't payed out here, winners claim with claim_winnings or anyone can pay them out with process_payouts
+    // A result that can't be recorded is reported and skipped rather than stopping the rest, as are the remaining results if the gas runs out
+    #[private]
+    pub fn settle_matches(&mut self, results: Vec<(String, MatchResult)>) -> Vec<MatchSettlement> {
+        let mut settlements: Vec<MatchSettlement> = Vec::new();
+        let mut settled: u32 = 0;
+
+        for (match_id, result) in results {
+            let error: Option<String> = if env::prepaid_gas() - env::used_gas() < GAS_PER_RESULT {
+                Some("There wasn't enough gas left, submit this result again".to_string())
+            } else {
+                self.result_error(&match_id)
+            };
+
+            if error.is_none() {
+                self.record_result(&match_id, result.red_score, result.blue_score);
+                settled += 1;
+            }
+            settlements.push(MatchSettlement{match_id, settled: error.is_none(), error});
+        }
+
+        log!("{} of {} results have been recorded without a challenge window", settled, settlements.len());
+        settlements
+    }
+
+    // Call function that allows a bettor to be payed out for their bets on a complete match
+    pub fn claim_winnings(&mut self, match_id: String) {
+        let bettor: AccountId = env::predecessor_account_id();
+        let mut current_match: Match = self.complete_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+
+        let (mut processed, payed): (usize, f64) = current_match.pay_out_bets(usize::MAX, Some(&bettor));
+        processed += self.settle_side_bets(&match_id, &current_match, usize::MAX, Some(&bettor)); // Pays out their parlays and side market bets
+        require!(processed > 0, "You have no bets on that match waiting to be payed out");
+        self.bet_counter -= payed; // The winnings are no longer owed
+
+        self.complete_matches.insert(&match_id, &current_match); // Updates the match
+        log!("{} has been payed out for {} bets", payed, processed)
+    }
+
+    // Call function that anyone can call to pay out up to limit bets on a complete match, so a match with many bets can be payed over several calls
+    pub fn process_payouts(&mut self, match_id: String, limit: u32) {
+        let mut current_match: Match = self.complete_matches.get(&match_id).unwrap_or_else(|| panic!("No match exists with match)id: {}", match_id)); // Finds the desired match, panics if doesn't find the match
+
+        let (mut processed, payed): (usize, f64) = current_match.pay_out_bets(limit as usize, None);
+        processed += self.settle_side_bets(&match_id, &current_match, limit as usize - processed, None); // Then the parlays and side market bets
+        self.bet_counter -= payed; // The winnings are no longer owed
+
+        self.complete_matches.insert(&match_id, &current_match); // Updates the match
+        let unpaid: usize = current_match.unpaid_bets() + self.unsettled_parlays(&match_id) + self.unsettled_market_bets(&match_id);
+        log!("{} bets have been processed, {} are still waiting to be payed out", processed, unpaid)
+    }
+}
+
+
+impl MatchList { // Helper functions for settlement
+
+    // Returns why a result can't be recorded for a match, or None if it can
+    pub(crate) fn result_error(&self, match_id: &String) -> Option<String> {
+        match self.in_progress_matches.get(match_id) {
+            None => Some(format!("No match in progress exists with match_id: {}", match_id)),
+            Some(current_match) if current_match.proposed_result.is_some() => Some("A result has already been proposed for that match".to_string()),
+            Some(_) => None,
+        }
+    }
+
+    // Records the result of a match in progress and moves it to complete, the winning bets are owed until they are payed out
+    // A tied match has no winner, its bets are pushed and their stakes are owed instead
+    // Nothing is payed out here, so the parlays and side markets of the match are settled when its bets are claimed or processed
+    pub(crate) fn record_result(&mut self, match_id: &String, red_score: u32, blue_score: u32) {
+        let mut current_match: Match = self.in_progress_matches.get(match_id).unwrap();
+        let winning_alliance: Option<AllianceColour> = match red_score.cmp(&blue_score) {
+            Ordering::Greater => Some(AllianceColour::Red),
+            Ordering::Less => Some(AllianceColour::Blue),
+            Ordering::Equal => None, // Tie
+        };
+
+        current_match.close_in_play(); // Returns the in-play bets that were never priced
+        self.bet_counter -= current_match.promised_winnings.abs() + current_match.in_play_promised_winnings(); // Swaps the promised winnings of the bets before and during the match for what is owed
+
+        current_match.winner = winning_alliance; // Sets the winning alliance
+        current_match.red_score = Some(red_score);
+        current_match.blue_score = Some(blue_score);
+        current_match.match_state = MatchState::Complete;
+        self.bet_counter += current_match.owed_winnings(); // Holds the winnings until they are payed out
+
+        self.complete_matches.insert(match_id, &current_match); // Inserts the match into the complete_matches
+        self.in_progress_matches.remove(match_id); // Removes the match from in_progress_matches
+    }
+
+    // Settles up to limit of the parlays and side market bets on a complete match, only those of the bettor if one is given
+    // Parlay legs on a tied match are voided and the handicap and total markets are settled from the scores. Returns the number processed
+    pub(crate) fn settle_side_bets(&mut self, match_id: &String, current_match: &Match, limit: usize, bettor: Option<&AccountId>) -> usize {
+        let processed: usize = self.settle_parlay_legs(match_id, current_match.winner, limit, bettor);
+        let scores: Option<(u32, u32)> = current_match.red_score.zip(current_match.blue_score);
+        processed + self.settle_match_markets(match_id, scores, limit - processed, bettor)
+    }
+}
+
+
+impl Match { // Helper functions for paying out a complete match
+
+    // Returns how much the house owes the winning bets that are yet to be payed, or the stakes of every bet after a tie
+    // Pari-mutuel and exchange winnings are payed from the stakes instead
+    fn owed_winnings(&self) -> f64 {
+        if self.pricing_model().settles_at_finish() {
+            return 0.0
+        }
+        let unpaid = self.bets.iter().filter(|bet| bet.payed_out == PayedOut::YetToBePayed);
+        match self.winner {
+            Some(winner) => unpaid.filter(|bet| bet.decision == winner).map(|bet| bet.potential_winnings).sum(),
+            None => unpaid.map(|bet| bet.bet_amount).sum(),
+        }
+    }
+
+    // Pays out up to limit unsettled bets and matched bets, only those of the bettor if one is given
+    // Returns the number of bets settled and how much of the owed winnings were payed
+    pub(crate) fn pay_out_bets(&mut self, limit: usize, bettor: Option<&AccountId>) -> (usize, f64) {
+        let winner: Option<AllianceColour> = self.winner;
+        let model: Box<dyn PricingModel> = self.pricing_model();
+        let pools: Vec<f64> = self.pools();
+        // Nobody backed the winner of a pari-mutuel pool so there is no one to share it, or the match was tied
+        let no_winners: bool = winner.is_none_or(|winner| model.settles_at_finish() && pools[winner.index()] == 0.0);
+
+        let mut processed: usize = 0;
+        let mut payed: f64 = 0.0;
+        for bet in self.bets.iter_mut().filter(|bet| bet.payed_out == PayedOut::YetToBePayed && bettor.is_none_or(|bettor| &bet.bettor == bettor)) {
+            if processed == limit {
+                break
+            }
+            if no_winners { // Returns every stake
+                transfer_usdc(bet.bettor.clone(), bet.bet_amount, "Return funds", near_sdk::Gas(30000000000000));
+                if winner.is_none() && !model.settles_at_finish() {
+                    payed += bet.bet_amount; // The stake of a pushed bet was owed by the house
+                }
+                bet.payed_out = PayedOut::ReturnPay;
+            } else if Some(bet.decision) == winner { // Checks they bet on the winner
+                let winnings: f64 = model.settle(&pools, bet.decision.index(), bet.bet_amount, bet.potential_winnings); // Gets the amount they win
+                transfer_usdc(bet.bettor.clone(), winnings, "Winnings", near_sdk::Gas(30000000000000));
+                if !model.settles_at_finish() {
+                    payed += bet.potential_winnings;
+                }
+                bet.potential_winnings = winnings; // Records what was actually payed
+                bet.payed_out = PayedOut::Payed;
+            } else {
+                bet.payed_out = PayedOut::NotPayed;
+            }
+            processed += 1;
+        }
+
+        processed += self.settle_matched_bets(winner, limit - processed, bettor); // Pays out the exchange bets between users
+        (processed, payed)
+    }
+
+    // Returns the number of bets and matched bets that are yet to be payed out
+    fn unpaid_bets(&self) -> usize {
+        self.bets.iter().filter(|bet| bet.payed_out == PayedOut::YetToBePayed).count()
+            + self.unpaid_matched_bets()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    // Returns a complete match with the bets placed on it, each bet is the bettor, decision, stake and potential winnings
+    fn complete_match(pricing: PricingKind, house_margin: f64, bets: &[(&str, AllianceColour, f64, f64)], winner: Option<AllianceColour>) -> Match {
+        testing_env!(VMContextBuilder::new().build());
+        let mut pools: Vec<f64> = vec![0.0, 0.0];
+        for (_, decision, bet_amount, _) in bets {
+            pools[decision.index()] += bet_amount;
+        }
+        Match{
+            bets: bets.iter().map(|(bettor, decision, bet_amount, potential_winnings)| Bet{bettor: bettor.parse().unwrap(), decision: *decision, bet_amount: *bet_amount, potential_winnings: *potential_winnings, payed_out: PayedOut::YetToBePayed, odds_version: None, in_play: false}).collect(),
+            red_alliance: Alliance{colour: AllianceColour::Red, teams: vec!["1234A".to_string()]},
+            blue_alliance: Alliance{colour: AllianceColour::Blue, teams: vec!["5678B".to_string()]},
+            event: None,
+            division: "Science".to_string(),
+            round: 1,
+            match_number: 1,
+            metadata: MatchMetadata::default(),
+            red_total_bets: pools[0],
+            blue_total_bets: pools[1],
+            promised_winnings: 0.0,
+            house_margin,
+            virtual_liquidity: 0.0,
+            pricing,
+            orders: Vec::new(),
+            matched_bets: Vec::new(),
+            odds_history: OddsHistory::new(0, 0),
+            in_play: None,
+            winner,
+            red_score: None,
+            blue_score: None,
+            proposed_result: None,
+            match_state: MatchState::Complete,
+        }
+    }
+
+    // Returns the account and amount of every USDC transfer made so far
+    fn transfers() -> Vec<(String, f64)> {
+        get_created_receipts().iter().flat_map(|receipt| receipt.actions.iter()).filter_map(|action| match action {
+            VmAction::FunctionCall{args, ..} => {
+                let args: serde_json::Value = serde_json::from_slice(args).unwrap();
+                Some((args["receiver_id"].as_str().unwrap().to_string(), args["amount"].as_str().unwrap().parse::<f64>().unwrap() / ONE_USDC))
+            }
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn pari_mutuel_with_no_winning_stakes_returns_every_stake() {
+        let bets = [("alice.near", AllianceColour::Blue, 100.0, 0.0), ("bob.near", AllianceColour::Blue, 50.0, 0.0)];
+        let mut current_match: Match = complete_match(PricingKind::PariMutuel, 0.1, &bets, Some(AllianceColour::Red));
+
+        let (processed, payed) = current_match.pay_out_bets(10, None);
+        assert_eq!(processed, 2);
+        assert_eq!(payed, 0.0); // The house owed nothing on a pari-mutuel match
+        assert!(current_match.bets.iter().all(|bet| bet.payed_out == PayedOut::ReturnPay));
+        assert_eq!(transfers(), vec![("alice.near".to_string(), 100.0), ("bob.near".to_string(), 50.0)]);
+    }
+
+    #[test]
+    fn tied_match_returns_every_stake() {
+        let bets = [("alice.near", AllianceColour::Red, 100.0, 190.0), ("bob.near", AllianceColour::Blue, 50.0, 95.0)];
+        let mut current_match: Match = complete_match(PricingKind::LogIntegral, 0.05, &bets, None);
+        assert_eq!(current_match.owed_winnings(), 150.0); // The stakes are held until they are payed
+
+        let (processed, payed) = current_match.pay_out_bets(10, None);
+        assert_eq!(processed, 2);
+        assert_eq!(payed, 150.0); // Comes off the bet_counter
+        assert!(current_match.bets.iter().all(|bet| bet.payed_out == PayedOut::ReturnPay));
+        assert_eq!(transfers(), vec![("alice.near".to_string(), 100.0), ("bob.near".to_string(), 50.0)]);
+    }
+}
+
@@ -0,0 +1,110 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, AccountId, near_bindgen, require, log};
+
+use crate::*;
+
+const ELO_SCALE: f64 = 400.0; // Rating difference at which the higher rated alliance is ten times as likely to win
+
+// Struct that holds the details of a registered team
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Team {
+    team_number: String, // Team number e.g. "1234A"
+    name: String,
+    organisation: String,
+    rating: f64, // Elo style rating used to seed the opening odds of matches
+}
+
+
+#[near_bindgen]
+impl MatchList { // Implementation of the team registry
+
+    // Private call function that allows the contract account to give an account the reporter role
+    #[private]
+    pub fn add_reporter(&mut self, account_id: AccountId) {
+        self.reporters.insert(&account_id);
+        log!("{} is now a reporter", account_id)
+    }
+
+    // Private call function that allows the contract account to take the reporter role away from an account
+    #[private]
+    pub fn remove_reporter(&mut self, account_id: AccountId) {
+        require!(self.reporters.remove(&account_id), "That account is not a reporter");
+        log!("{} is no longer a reporter", account_id)
+    }
+
+    // Private call function that allows the contract account to add a team to the registry or change its details
+    #[private]
+    pub fn register_team(&mut self, team_number: String, name: String, organisation: String, rating: f64) {
+        require!(rating.is_finite(), "The rating must be a number");
+        let team: Team = Team{team_number: team_number.clone(), name, organisation, rating};
+        self.teams.insert(&team_number, &team); // Adds the team to the registry, replacing any old details
+        log!("Team {} has been registered", team_number)
+    }
+
+    // Call function that allows a reporter to update the ratings of teams after a match has finished
+    pub fn update_team_ratings(&mut self, ratings: Vec<(String, f64)>) {
+        self.assert_reporter();
+
+        for (team_number, rating) in ratings { // Loops through all the new ratings
+            require!(rating.is_finite(), "The rating must be a number");
+            let mut team: Team = self.teams.get(&team_number).unwrap_or_else(|| panic!("No team is registered with team number: {}", team_number));
+            team.rating = rating;
+            self.teams.insert(&team_number, &team); // Updates the team
+        }
+    }
+
+    // View function that allows the user to view the details of a single team
+    pub fn view_team(&self, team_number: String) -> Team {
+        self.teams.get(&team_number).unwrap_or_else(|| panic!("No team is registered with team number: {}", team_number))
+    }
+
+    // View function that allows the user to view all registered teams
+    pub fn view_teams(&self) -> Vec<Team> {
+        self.teams.values().collect()
+    }
+
+    // View function that allows the user to view all accounts with the reporter role
+    pub fn view_reporters(&self) -> Vec<AccountId> {
+        self.reporters.to_vec()
+    }
+}
+
+
+impl MatchList { // Helper functions for the team registry
+
+    // Panics if the caller is neither a reporter nor the contract account
+    pub(crate) fn assert_reporter(&self) {
+        let caller: AccountId = env::predecessor_account_id();
+        require!(caller == env::current_account_id() || self.reporters.contains(&caller), "Only a reporter can call this function");
+    }
+
+    // Finds the probability of each alliance winning from the registered ratings of their teams
+    // Each alliance is rated as the average of its teams, and the ratings are compared using the Elo formula
+    pub(crate) fn rating_probabilities(&self, red_teams: &[String], blue_teams: &[String]) -> (f64, f64) {
+        let red_rating: f64 = self.alliance_rating(red_teams);
+        let blue_rating: f64 = self.alliance_rating(blue_teams);
+        let prob_red: f64 = 1.0 / (1.0 + 10f64.powf((blue_rating - red_rating) / ELO_SCALE));
+
+        (prob_red, 1.0 - prob_red)
+    }
+
+    // Finds the probability of each team winning a field of registered teams, or None if one of them isn't registered
+    // Each team's chance is in proportion to 10^(rating / 400), which gives the Elo formula between any two of them
+    pub(crate) fn field_probabilities(&self, team_numbers: &[String]) -> Option<Vec<f64>> {
+        let strengths: Vec<f64> = team_numbers.iter()
+            .map(|team_number| self.teams.get(team_number).map(|team| 10f64.powf(team.rating / ELO_SCALE)))
+            .collect::<Option<Vec<f64>>>()?;
+        let total: f64 = strengths.iter().sum();
+        Some(strengths.iter().map(|strength| strength / total).collect())
+    }
+
+    // Finds the average rating of the teams in an alliance, panics if a team isn't registered
+    fn alliance_rating(&self, teams: &[String]) -> f64 {
+        let total: f64 = teams.iter()
+            .map(|team_number| self.teams.get(team_number).unwrap_or_else(|| panic!("No team is registered with team number: {}", team_number)).rating)
+            .sum();
+        total / teams.len() as f64
+    }
+}
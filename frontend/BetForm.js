@@ -3,7 +3,7 @@ import { useState, useEffect } from 'react';
 const BetForm = ({currentMatch, theContract}) => {
     const [betAmount, setBetAmount] = useState('');
     const [team, setTeam] = useState('');
-    const matchName = currentMatch[0]
+    const matchName = currentMatch.match_id
     const [potentialWinnings, setPotentialWinnings] = useState(0.0);
 
     const handleSubmit = (e) => {
@@ -38,15 +38,15 @@ const BetForm = ({currentMatch, theContract}) => {
                 value = { betAmount }
                 onChange={(e) => setBetAmount(e.target.value)}/>
 
-                <label className='bet-text'>Team</label>
+                <label className='bet-text'>Alliance</label>
                 <select 
                     className='bet-select'
                     value={ team }
                     onChange={(e) => setTeam(e.target.value)}
                 >
-                    <option value={'default'}>Select Team</option>
-                    <option value={ currentMatch[1] }>{ currentMatch[1] }</option>
-                    <option value={ currentMatch[3] }>{ currentMatch[3] }</option>
+                    <option value={'default'}>Select Alliance</option>
+                    <option value={ 'Red' }>Red: { currentMatch.red_teams.join(' + ') }</option>
+                    <option value={ 'Blue' }>Blue: { currentMatch.blue_teams.join(' + ') }</option>
                 </select>
 
                 <p className='pot-win'>Ⓝ Potential Winnings <br />{ potentialWinnings }</p>
@@ -2,7 +2,7 @@ import { useState, useEffect } from 'react';
 import BetForm from './BetForm';
 
 const MatchList = ({ isSignedIn, theContract}) => {
-  const [currentMatch, setCurrentMatch] = useState(['', '', '', '', '', '', '', '']);
+  const [currentMatch, setCurrentMatch] = useState({ match_id: '', red_teams: [], red_odds: 0, blue_teams: [], blue_odds: 0 });
   const [matchSelected, setMatchSelected] = useState(false)
   const showBetForm = isSignedIn && matchSelected
   const [matches, setMatches] = useState([]);
@@ -24,11 +24,11 @@ const MatchList = ({ isSignedIn, theContract}) => {
       <div className="left">
           <div className="match-list">
             {matches.map(match => (
-              <div key={match[0]}>
+              <div key={match.match_id}>
                 
                 {<button className="match-preview" onClick={(e) => handleClick(e, match, true)} disabled={!isSignedIn}><div>
-                    <h2> {match[1]} vs {match[3]} </h2>
-                    <p> {match[2]} to {match[4]} </p> 
+                    <h2> {match.red_teams.join(' + ')} vs {match.blue_teams.join(' + ')} </h2>
+                    <p> {match.red_odds} to {match.blue_odds} </p> 
                   </div>
                 </button> }
 
@@ -16,7 +16,7 @@ export class TheContract {
   async getPotentialWinnings({ matchId, team, betAmount }) {
     // Views potential winnings
     console.log(matchId)
-    const potentialWinnings = await this.wallet.viewMethod({ contractId: this.contractId, method: "view_potential_winnings", args: { match_id: matchId, team: team, bet_amount: betAmount}})
+    const potentialWinnings = await this.wallet.viewMethod({ contractId: this.contractId, method: "view_potential_winnings", args: { match_id: matchId, alliance: team, bet_amount: betAmount}})
     return potentialWinnings
   }
 